@@ -1,10 +1,13 @@
 use crate::commands::{backup, init};
-use crate::error::{JotError, JotResult};
-use crate::storage::config::Config;
-use crate::{commands, storage};
+use crate::error::JotResult;
+use crate::storage::{config::Config, Journal};
+use crate::{commands, storage, utils};
 use clap::{Parser, Subcommand};
 
-use commands::{add, edit, export, remove, search, view};
+use commands::{
+    add, agenda, checklist, dupes, edit, export, history, related, remove, retag, search, tags,
+    task, view, views,
+};
 
 /// A command-line journaling tool for quick note-taking and organization
 #[derive(Parser)]
@@ -51,6 +54,12 @@ enum Commands {
         args: edit::EditArgs,
     },
 
+    /// Add, remove or rename tags on every entry matching a filter query
+    Retag {
+        #[clap(flatten)]
+        args: retag::RetagArgs,
+    },
+
     /// Search through journal entries
     #[command(visible_alias = "find", visible_alias = "s")]
     Search {
@@ -58,6 +67,13 @@ enum Commands {
         args: search::SearchArgs,
     },
 
+    /// List tags with usage counts, or rename, merge and delete them
+    #[command(visible_alias = "t")]
+    Tags {
+        #[clap(flatten)]
+        args: tags::TagsArgs,
+    },
+
     /// Export journal entries to various formats
     #[command(visible_alias = "dump", visible_alias = "ex")]
     Export {
@@ -70,6 +86,83 @@ enum Commands {
         #[clap(flatten)]
         args: backup::BackupArgs,
     },
+
+    /// List open tasks by priority
+    Todo {
+        #[clap(flatten)]
+        args: task::TodoArgs,
+    },
+
+    /// Mark tasks as done
+    Done {
+        #[clap(flatten)]
+        args: task::TaskIdsArgs,
+    },
+
+    /// Mark done or cancelled tasks as open again
+    Reopen {
+        #[clap(flatten)]
+        args: task::TaskIdsArgs,
+    },
+
+    /// Check off checklist items (`- [ ] item` lines) of an entry
+    Check {
+        #[clap(flatten)]
+        args: checklist::CheckArgs,
+    },
+
+    /// Uncheck checklist items of an entry
+    Uncheck {
+        #[clap(flatten)]
+        args: checklist::CheckArgs,
+    },
+
+    /// List overdue, due today and upcoming tasks by day
+    Agenda {
+        #[clap(flatten)]
+        args: agenda::AgendaArgs,
+    },
+
+    /// List the entries most similar to an entry by text and tags
+    Related {
+        #[clap(flatten)]
+        args: related::RelatedArgs,
+    },
+
+    /// Find near-duplicate entries and optionally merge them
+    Dupes {
+        #[clap(flatten)]
+        args: dupes::DupesArgs,
+    },
+
+    /// List saved searches, run with `xlog view @name`
+    Views {
+        #[clap(flatten)]
+        args: views::ViewsArgs,
+    },
+
+    /// Rebuild the search index from scratch
+    Reindex,
+
+    /// Undo the last journal-modifying commands
+    #[command(visible_alias = "u")]
+    Undo {
+        #[clap(flatten)]
+        args: history::UndoArgs,
+    },
+
+    /// Redo commands reverted with undo
+    Redo {
+        #[clap(flatten)]
+        args: history::UndoArgs,
+    },
+
+    /// List recent journal-modifying commands
+    #[command(visible_alias = "history")]
+    Log {
+        #[clap(flatten)]
+        args: history::LogArgs,
+    },
 }
 
 /// Runs a single command based on the parsed CLI arguments
@@ -94,25 +187,28 @@ pub fn run(config: &Config) -> JotResult<()> {
     match cli.command {
         Commands::Init { args } => commands::init::execute(args),
         Commands::Add { args } => {
-            let mut journal = storage::load_journal()?;
-            commands::add::execute(&mut journal, args, config)
+            run_recorded(|journal| commands::add::execute(journal, args, config))
         }
         Commands::Remove { args } => {
-            let mut journal = storage::load_journal()?;
-            commands::remove::execute(&mut journal, args)
+            run_recorded(|journal| commands::remove::execute(journal, args, config))
         }
         Commands::View { args } => {
             let journal = storage::load_journal()?;
             commands::view::execute(&journal, args, config)
         }
         Commands::Edit { args } => {
-            let mut journal = storage::load_journal()?;
-            commands::edit::execute(&mut journal, args)
+            run_recorded(|journal| commands::edit::execute(journal, args, config))
+        }
+        Commands::Retag { args } => {
+            run_recorded(|journal| commands::retag::execute(journal, args, config))
         }
         Commands::Search { args } => {
             let journal = storage::load_journal()?;
             commands::search::execute(&journal, args, config)
         }
+        Commands::Tags { args } => {
+            run_recorded(|journal| commands::tags::execute(journal, args, config))
+        }
         Commands::Export { args } => {
             let mut journal = storage::load_journal()?;
             commands::export::execute(&mut journal, args, config)
@@ -121,5 +217,73 @@ pub fn run(config: &Config) -> JotResult<()> {
             let mut journal = storage::load_journal()?;
             commands::backup::execute(&mut journal, args)
         }
+        Commands::Todo { args } => {
+            let journal = storage::load_journal()?;
+            commands::task::todo(&journal, args, config)
+        }
+        Commands::Done { args } => run_recorded(|journal| commands::task::done(journal, args)),
+        Commands::Reopen { args } => run_recorded(|journal| commands::task::reopen(journal, args)),
+        Commands::Check { args } => {
+            run_recorded(|journal| commands::checklist::check(journal, args))
+        }
+        Commands::Uncheck { args } => {
+            run_recorded(|journal| commands::checklist::uncheck(journal, args))
+        }
+        Commands::Agenda { args } => {
+            let journal = storage::load_journal()?;
+            let exit_code = args.exit_code;
+            let due = commands::agenda::execute(&journal, args)?;
+            if exit_code && due > 0 {
+                std::process::exit(commands::agenda::TASKS_DUE_EXIT_CODE);
+            }
+            Ok(())
+        }
+        Commands::Related { args } => {
+            let journal = storage::load_journal()?;
+            commands::related::execute(&journal, args, config)
+        }
+        Commands::Dupes { args } if args.merge => {
+            run_recorded(|journal| commands::dupes::execute(journal, args, config))
+        }
+        Commands::Dupes { args } => {
+            let mut journal = storage::load_journal()?;
+            commands::dupes::execute(&mut journal, args, config)
+        }
+        Commands::Views { args } => commands::views::execute(args, config),
+        Commands::Reindex => {
+            let journal = storage::load_journal()?;
+            commands::reindex::execute(&journal)
+        }
+        Commands::Undo { args } => {
+            let mut journal = storage::load_journal()?;
+            commands::history::undo(&mut journal, args)
+        }
+        Commands::Redo { args } => {
+            let mut journal = storage::load_journal()?;
+            commands::history::redo(&mut journal, args)
+        }
+        Commands::Log { args } => {
+            let journal = storage::load_journal()?;
+            commands::history::log(&journal, args)
+        }
     }
 }
+
+/// Runs a journal-modifying command and records its changes so they can be undone
+fn run_recorded(command: impl FnOnce(&mut Journal) -> JotResult<()>) -> JotResult<()> {
+    let mut journal = storage::load_journal()?;
+    let before = journal.entries().clone();
+
+    command(&mut journal)?;
+
+    storage::history::record(&journal, &before, command_line())
+}
+
+/// The arguments the program was started with, quoted where needed
+fn command_line() -> String {
+    std::env::args()
+        .skip(1)
+        .map(|arg| utils::quote_arg(&arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
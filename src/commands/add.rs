@@ -1,51 +1,213 @@
 use colored::Colorize;
+use dialoguer::MultiSelect;
+use std::io::IsTerminal;
 
 use crate::error::{JotError, JotResult};
 use crate::storage::config::Config;
-use crate::storage::{self, Entry, Journal, Tag};
+use crate::storage::{self, Entry, Journal, Priority, Tag, DEFAULT_KIND};
+use crate::utils::{dates, fields, kinds, tags};
+
+/// How many tags are suggested at most for an entry added without any
+const SUGGESTIONS: usize = 3;
 
 #[derive(clap::Args, Clone)]
 pub struct AddArgs {
     pub content: String,
+    /// Title of the entry, defaults to the first line of the content
+    #[clap(short, long)]
+    pub title: Option<String>,
+    /// Set a metadata field, e.g. `--field hours=2.5` (can be repeated)
+    #[clap(long = "field", value_name = "KEY=VALUE")]
+    pub fields: Vec<String>,
+    /// Kind of entry, e.g. task, log, bookmark or one defined in the config
+    #[clap(short, long, default_value = DEFAULT_KIND)]
+    pub kind: String,
+    /// Priority of a task
+    #[clap(short, long, value_enum)]
+    pub priority: Option<Priority>,
+    /// Due date of a task, e.g. 2024-06-01, friday or 3d
+    #[clap(short, long)]
+    pub due: Option<String>,
+    /// Add the suggested tags without asking when the entry has no tags
+    #[clap(long)]
+    pub auto_tag: bool,
+}
+
+/// Suggests tags for an entry added without any, based on the tags of entries
+/// using the same words.
+///
+/// # Returns
+///
+/// Every suggested tag with `--auto-tag`, otherwise the ones picked from the
+/// suggestions. Nothing is suggested when input isn't a terminal.
+fn suggested_tags(journal: &Journal, content: &str, auto_tag: bool) -> JotResult<Vec<Tag>> {
+    if !auto_tag && !std::io::stdin().is_terminal() {
+        return Ok(Vec::new());
+    }
+
+    let index = storage::index::update_index(journal)?;
+    let suggestions = tags::suggest_tags(content, journal.get_entries(), &index, SUGGESTIONS);
+    if auto_tag || suggestions.is_empty() {
+        return Ok(suggestions);
+    }
+
+    let items = suggestions
+        .iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<_>>();
+    let picked = MultiSelect::new()
+        .with_prompt("No tags given. Add these suggested tags? (space toggles, enter confirms)")
+        .items(&items)
+        .defaults(&vec![true; items.len()])
+        .interact()
+        .map_err(|e| JotError::AddError(format!("Failed to get tag selection: {}", e)))?;
+    Ok(picked.into_iter().map(|i| suggestions[i].clone()).collect())
 }
 
 pub fn execute(journal: &mut Journal, args: AddArgs, config: &Config) -> JotResult<()> {
-    let content = args.content.trim();
+    let (mut entry_fields, content) = fields::extract_fields(args.content.trim());
+    for field in &args.fields {
+        let (key, value) = fields::parse_field_arg(field).map_err(JotError::AddError)?;
+        entry_fields.insert(key, value);
+    }
     if content.is_empty() {
         return Err(JotError::AddError("Entry cannot be empty".to_string()));
     }
 
-    let tags = extract_tags(content);
-    let body = extract_body(content, config);
+    let (kind_name, kind) = kinds::resolve_kind(&args.kind, config).map_err(JotError::AddError)?;
+    let default_tags = kind
+        .default_tags
+        .iter()
+        .filter_map(|t| tags::normalize_tag(t, &config.journal_cfg));
+    let extracted = tags::extract_tags(&content, &config.journal_cfg);
+    let suggested = if extracted.is_empty() {
+        suggested_tags(journal, &content, args.auto_tag)?
+    } else {
+        Vec::new()
+    };
+    let tags = tags::dedup_tags(
+        extracted
+            .into_iter()
+            .chain(default_tags)
+            .chain(suggested.iter().cloned()),
+    );
+    let body = extract_body(&content, config);
+
+    let id = journal.next_id();
+    let mut entry = Entry {
+        title: args
+            .title
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty()),
+        kind: kind_name,
+        fields: entry_fields,
+        ..Entry::new(id, body, tags)
+    };
+    entry.sync_task();
 
-    let entry = Entry::new(journal.next_id(), body, tags);
+    if args.priority.is_some() || args.due.is_some() {
+        let task = entry.task.as_mut().ok_or_else(|| {
+            JotError::AddError(
+                "--priority and --due only apply to tasks, add --kind task".to_string(),
+            )
+        })?;
+        if let Some(priority) = args.priority {
+            task.priority = priority;
+        }
+        if let Some(due) = &args.due {
+            let today = chrono::Local::now().date_naive();
+            task.due = Some(dates::parse_relative_date(due, today).ok_or_else(|| {
+                JotError::AddError(format!(
+                    "Invalid due date '{}': {}",
+                    due,
+                    dates::RELATIVE_DATE_HELP
+                ))
+            })?);
+        }
+    }
+
+    let missing = kinds::missing_fields(&entry, &kind);
+    if !missing.is_empty() {
+        return Err(JotError::AddError(format!(
+            "A {} needs the field(s) {}; set them with --field KEY=VALUE",
+            entry.kind,
+            missing.join(", ")
+        )));
+    }
     journal.add_entry(entry);
     storage::save_journal(journal)?;
 
-    println!(
-        "Entry {} added!",
-        format!("#{}", journal.next_id()).bold().green()
-    );
+    println!("Entry {} added!", format!("#{}", id).bold().green());
+    if args.auto_tag && !suggested.is_empty() {
+        let names = suggested.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        println!("{}", format!("Tagged with {}", names.join(" ")).dimmed());
+    }
 
     Ok(())
 }
 
-fn extract_tags(content: &str) -> Vec<Tag> {
+/// Removes inline `#tags` from the content when the config keeps tags out of
+/// the body. Lines and their indentation are kept, so checklists survive, and
+/// a line holding nothing but tags is dropped.
+fn extract_body(content: &str, config: &Config) -> String {
+    if !config.journal_cfg.body_tags {
+        return content.to_string();
+    }
+
     content
-        .split_whitespace()
-        .filter(|w| w.starts_with('#'))
-        .map(|w| Tag::new(w[1..].to_string()))
-        .collect()
+        .lines()
+        .filter_map(|line| {
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            let kept = words
+                .iter()
+                .filter(|w| !tags::is_inline_tag(w, &config.journal_cfg))
+                .copied()
+                .collect::<Vec<_>>();
+            if kept.len() == words.len() {
+                return Some(line.trim_end().to_string());
+            }
+            if kept.is_empty() {
+                return None;
+            }
+            let indent = &line[..line.len() - line.trim_start().len()];
+            Some(format!("{}{}", indent, kept.join(" ")))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
 }
 
-fn extract_body(content: &str, config: &Config) -> String {
-    if config.journal_cfg.body_tags {
-        content
-            .split_whitespace()
-            .filter(|w| !w.starts_with('#'))
-            .collect::<Vec<&str>>()
-            .join(" ")
-    } else {
-        content.to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::checklist;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_add_checklist_with_inline_tags() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut journal = Journal::new(temp_dir.path().join("journal.json"));
+        let mut config = Config::default();
+        config.journal_cfg.body_tags = true;
+        let args = AddArgs {
+            content: "Release #work\n- [ ] notes #docs\n- [ ] tag\n  - [ ] publish".to_string(),
+            title: None,
+            fields: Vec::new(),
+            kind: DEFAULT_KIND.to_string(),
+            priority: None,
+            due: None,
+            auto_tag: false,
+        };
+        execute(&mut journal, args, &config).unwrap();
+
+        let entry = &journal.get_entries()[0];
+        assert_eq!(
+            entry.body,
+            "Release\n- [ ] notes\n- [ ] tag\n  - [ ] publish"
+        );
+        assert_eq!(checklist::progress(&entry.body), Some((0, 3)));
+        assert_eq!(entry.tags.len(), 2);
+        assert_eq!(extract_body("#only #tags\nkept", &config), "kept");
     }
 }
@@ -0,0 +1,149 @@
+use chrono::{Days, NaiveDate};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::ops::Bound::{Excluded, Unbounded};
+
+use crate::{
+    error::JotResult,
+    storage::{Entry, Journal, Priority, TaskStatus},
+};
+
+/// Exit status of `agenda --exit-code` when tasks are overdue or due today,
+/// kept apart from errors (1) and invalid arguments (2)
+pub const TASKS_DUE_EXIT_CODE: i32 = 3;
+
+#[derive(clap::Args, Clone)]
+pub struct AgendaArgs {
+    /// Number of days ahead to list upcoming tasks for
+    #[clap(short, long, default_value_t = 7)]
+    pub days: u64,
+    /// Exit with status 3 if any task is overdue or due today, e.g. for a login
+    /// script; errors exit with status 1 and invalid arguments with 2
+    #[clap(long)]
+    pub exit_code: bool,
+}
+
+/// Open tasks with a due date up to the end of the window, grouped by due date
+fn group_by_day(entries: &[Entry], until: NaiveDate) -> BTreeMap<NaiveDate, Vec<&Entry>> {
+    let mut days: BTreeMap<NaiveDate, Vec<&Entry>> = BTreeMap::new();
+    for entry in entries {
+        let Some(task) = entry.task.as_ref().filter(|t| t.status.is_open()) else {
+            continue;
+        };
+        if let Some(due) = task.due.filter(|due| *due <= until) {
+            days.entry(due).or_default().push(entry);
+        }
+    }
+
+    for tasks in days.values_mut() {
+        tasks.sort_by_key(|e| std::cmp::Reverse(e.task.as_ref().map(|t| t.priority)));
+    }
+    days
+}
+
+fn print_day(day: NaiveDate, tasks: &[&Entry]) {
+    println!("  {}", day.format("%a %Y-%m-%d").to_string().bright_blue());
+    for entry in tasks {
+        let Some(task) = &entry.task else {
+            continue;
+        };
+        let status = match task.status {
+            TaskStatus::InProgress => "[~]".yellow(),
+            _ => "[ ]".normal(),
+        };
+        let priority = match task.priority {
+            Priority::High => " !high".red(),
+            Priority::Low => " !low".dimmed(),
+            Priority::Medium => "".normal(),
+        };
+        println!(
+            "    [{:>3}] {}{} {}",
+            entry.id,
+            status,
+            priority,
+            entry.display_title()
+        );
+    }
+}
+
+/// Prints overdue tasks, tasks due today and upcoming ones, each grouped by day.
+///
+/// # Returns
+///
+/// The number of tasks that are overdue or due today.
+pub fn execute(journal: &Journal, args: AgendaArgs) -> JotResult<usize> {
+    let today = chrono::Local::now().date_naive();
+    let until = today
+        .checked_add_days(Days::new(args.days))
+        .unwrap_or(NaiveDate::MAX);
+    let days = group_by_day(journal.get_entries(), until);
+
+    if days.is_empty() {
+        println!("{}", "Nothing due.".green());
+        return Ok(0);
+    }
+
+    let overdue = days.range(..today).collect::<Vec<_>>();
+    if !overdue.is_empty() {
+        println!("{}", "Overdue".red().bold());
+        for (day, tasks) in &overdue {
+            print_day(**day, tasks);
+        }
+    }
+
+    let due_today = days.get(&today);
+    if let Some(tasks) = due_today {
+        println!("{}", "Today".yellow().bold());
+        print_day(today, tasks);
+    }
+
+    let upcoming = days.range((Excluded(today), Unbounded)).collect::<Vec<_>>();
+    if !upcoming.is_empty() {
+        println!("{}", "Upcoming".bold());
+        for (day, tasks) in upcoming {
+            print_day(*day, tasks);
+        }
+    }
+
+    Ok(overdue.iter().map(|(_, tasks)| tasks.len()).sum::<usize>() + due_today.map_or(0, Vec::len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::TASK_KIND;
+
+    fn task(id: usize, due: Option<NaiveDate>, status: TaskStatus) -> Entry {
+        let mut entry = Entry::new(id, format!("task {}", id), vec![]);
+        entry.kind = TASK_KIND.to_string();
+        entry.sync_task();
+        if let Some(task) = &mut entry.task {
+            task.due = due;
+            task.set_status(status);
+        }
+        entry
+    }
+
+    #[test]
+    fn test_group_by_day() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 10, d);
+        let entries = vec![
+            task(0, day(10), TaskStatus::Open),
+            task(1, day(19), TaskStatus::InProgress),
+            task(2, day(19), TaskStatus::Done),
+            task(3, day(30), TaskStatus::Open),
+            task(4, None, TaskStatus::Open),
+            Entry::new(5, "note".to_string(), vec![]),
+        ];
+
+        let days = group_by_day(&entries, day(26).unwrap());
+        let ids = days
+            .iter()
+            .map(|(day, tasks)| (*day, tasks.iter().map(|e| e.id).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![(day(10).unwrap(), vec![0]), (day(19).unwrap(), vec![1])]
+        );
+    }
+}
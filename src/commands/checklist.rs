@@ -0,0 +1,79 @@
+use colored::Colorize;
+
+use crate::{
+    error::{JotError, JotResult},
+    storage::{self, Journal},
+    utils::checklist,
+};
+
+#[derive(clap::Args, Clone)]
+pub struct CheckArgs {
+    /// ID of the entry
+    pub id: usize,
+    /// Numbers of the checklist items, or unique parts of their text; lists
+    /// the items when left out
+    pub items: Vec<String>,
+}
+
+/// Checks off checklist items of an entry
+pub fn check(journal: &mut Journal, args: CheckArgs) -> JotResult<()> {
+    set_items(journal, args, true)
+}
+
+/// Unchecks checklist items of an entry
+pub fn uncheck(journal: &mut Journal, args: CheckArgs) -> JotResult<()> {
+    set_items(journal, args, false)
+}
+
+fn set_items(journal: &mut Journal, args: CheckArgs, checked: bool) -> JotResult<()> {
+    let mut entry = journal
+        .get_entry(args.id)
+        .cloned()
+        .ok_or_else(|| JotError::CommandError(format!("Entry with ID {} not found", args.id)))?;
+
+    let items = checklist::parse_checklist(&entry.body);
+    if items.is_empty() {
+        return Err(JotError::CommandError(format!(
+            "Entry {} has no checklist items (lines like '- [ ] item')",
+            args.id
+        )));
+    }
+
+    if args.items.is_empty() {
+        for item in &items {
+            let mark = if item.checked {
+                "[x]".green()
+            } else {
+                "[ ]".normal()
+            };
+            println!("{:>3}. {} {}", item.index, mark, item.text);
+        }
+        return Ok(());
+    }
+
+    let selected = args
+        .items
+        .iter()
+        .map(|query| checklist::find_item(&items, query).map_err(JotError::CommandError))
+        .collect::<JotResult<Vec<_>>>()?;
+
+    for item in &selected {
+        entry.body = checklist::set_checked(&entry.body, item.line, checked);
+        println!(
+            "{} {}",
+            if checked {
+                "[x]".green()
+            } else {
+                "[ ]".normal()
+            },
+            item.text
+        );
+    }
+
+    if let Some((done, total)) = checklist::progress(&entry.body) {
+        println!("{}/{} done", done, total);
+    }
+
+    journal.update_entry(entry);
+    storage::save_journal(journal)
+}
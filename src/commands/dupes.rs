@@ -0,0 +1,168 @@
+use colored::Colorize;
+use dialoguer::Confirm;
+use std::collections::HashSet;
+
+use crate::{
+    error::{JotError, JotResult},
+    storage::{self, config::Config, Entry, Journal},
+    utils::{self, similarity},
+};
+
+#[derive(clap::Args, Clone)]
+pub struct DupesArgs {
+    /// How similar the text of two entries must be to count as duplicates,
+    /// from 0 to 1
+    #[clap(short, long, default_value_t = 0.9)]
+    pub threshold: f64,
+    /// Offer to merge each pair of duplicates into the older entry
+    #[clap(short, long)]
+    pub merge: bool,
+    /// Merge without asking for confirmation
+    #[clap(short, long, requires = "merge")]
+    pub yes: bool,
+}
+
+/// Combines a duplicate into the entry it duplicates: tags and fields are
+/// joined, and the duplicate's body is kept too unless one body already
+/// contains the other
+fn merge_entries(keep: &Entry, duplicate: &Entry) -> Entry {
+    let mut merged = keep.clone();
+
+    let (body, other) = (keep.body.trim(), duplicate.body.trim());
+    if body.contains(other) {
+        // Nothing to add
+    } else if other.contains(body) {
+        merged.body = duplicate.body.clone();
+    } else {
+        merged.body = format!("{}\n\n{}", body, other);
+    }
+
+    if merged.title.is_none() {
+        merged.title = duplicate.title.clone();
+    }
+    for tag in &duplicate.tags {
+        if !merged.tags.contains(tag) {
+            merged.tags.push(tag.clone());
+        }
+    }
+    for (key, value) in &duplicate.fields {
+        merged
+            .fields
+            .entry(key.clone())
+            .or_insert_with(|| value.clone());
+    }
+    merged
+}
+
+fn confirm_merge(keep: usize, duplicate: usize) -> JotResult<bool> {
+    Confirm::new()
+        .with_prompt(format!("Merge #{} into #{}?", duplicate, keep))
+        .default(false)
+        .interact()
+        .map_err(|e| {
+            JotError::CommandError(format!(
+                "Failed to get confirmation ({}); pass --yes to skip it",
+                e
+            ))
+        })
+}
+
+/// Lists pairs of near-duplicate entries, and merges them if asked to
+pub fn execute(journal: &mut Journal, args: DupesArgs, config: &Config) -> JotResult<()> {
+    if !(0.0..=1.0).contains(&args.threshold) {
+        return Err(JotError::CommandError(format!(
+            "Invalid threshold {}: it must be between 0 and 1",
+            args.threshold
+        )));
+    }
+
+    let pairs = similarity::duplicates(journal.get_entries(), args.threshold);
+    if pairs.is_empty() {
+        println!("No duplicates found.");
+        return Ok(());
+    }
+
+    let mut merged = HashSet::new();
+    for (keep, duplicate, score) in &pairs {
+        // Either entry may already have been merged into another one
+        if merged.contains(keep) || merged.contains(duplicate) {
+            continue;
+        }
+        let (Some(first), Some(second)) = (journal.get_entry(*keep), journal.get_entry(*duplicate))
+        else {
+            continue;
+        };
+
+        println!(
+            "{}",
+            format!(
+                "#{} and #{} are {:.0}% similar",
+                keep,
+                duplicate,
+                score * 100.0
+            )
+            .yellow()
+        );
+        println!("{}", utils::format_entry(first, config));
+        println!("{}", utils::format_entry(second, config));
+
+        if args.merge && (args.yes || confirm_merge(*keep, *duplicate)?) {
+            let entry = merge_entries(first, second);
+            journal.update_entry(entry);
+            journal.remove_entry(*duplicate);
+            merged.insert(*duplicate);
+            println!(
+                "{}",
+                format!("Merged #{} into #{}", duplicate, keep).green()
+            );
+        }
+    }
+
+    if merged.is_empty() {
+        println!("{} possible duplicates found", pairs.len());
+    } else {
+        storage::save_journal(journal)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Tag;
+
+    #[test]
+    fn test_merge_entries() {
+        let mut keep = Entry::new(
+            1,
+            "Deployed the API".to_string(),
+            vec![Tag::new("work".to_string())],
+        );
+        keep.fields.insert("hours".to_string(), "2".to_string());
+        let mut duplicate = Entry::new(
+            2,
+            "Deployed the API to production".to_string(),
+            vec![Tag::new("work".to_string()), Tag::new("ops".to_string())],
+        );
+        duplicate
+            .fields
+            .insert("hours".to_string(), "3".to_string());
+        duplicate
+            .fields
+            .insert("project".to_string(), "apollo".to_string());
+
+        let merged = merge_entries(&keep, &duplicate);
+        assert_eq!(merged.id, 1);
+        assert_eq!(merged.body, "Deployed the API to production");
+        assert_eq!(merged.tags.len(), 2);
+        assert_eq!(merged.fields["hours"], "2");
+        assert_eq!(merged.fields["project"], "apollo");
+
+        let other = Entry::new(3, "Rolled back".to_string(), Vec::new());
+        assert_eq!(
+            merge_entries(&keep, &other).body,
+            "Deployed the API\n\nRolled back"
+        );
+        assert_eq!(merge_entries(&duplicate, &keep).body, duplicate.body);
+    }
+}
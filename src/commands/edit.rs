@@ -1,54 +1,860 @@
 use crate::{
     error::{JotError, JotResult},
-    storage::{self, Entry, Journal, Tag},
-    utils,
+    storage::{
+        self,
+        config::{Config, JournalConfig},
+        Entry, Journal, Priority, Tag, TaskStatus,
+    },
+    utils::{self, dates, fields, filter::EntryFilter, kinds, tags},
 };
+use chrono::NaiveDate;
+use clap::ValueEnum;
 use colored::Colorize;
+use dialoguer::Editor;
+use std::collections::BTreeMap;
 
-#[derive(clap::Args, Clone)]
+const FRONT_MATTER_DELIMITER: &str = "---";
+
+#[derive(clap::Args, Clone, Default)]
 pub struct EditArgs {
-    pub id: usize,
+    /// IDs of the entries to edit
+    pub ids: Vec<usize>,
+    /// Also edit every entry whose body contains this text (case-insensitive)
+    #[clap(short, long = "match", value_name = "TEXT")]
+    pub matching: Option<String>,
+    /// Also edit every entry matching a filter query, e.g. "tag:work from:2024-01-01 deploy"
+    #[clap(short, long = "where", value_name = "QUERY")]
+    pub where_query: Option<String>,
+    /// Set the title; an empty title falls back to the first line of the body
+    #[clap(long)]
+    pub title: Option<String>,
+    /// Replace the body
+    #[clap(long)]
+    pub body: Option<String>,
+    /// Append a line to the end of the body
+    #[clap(long)]
+    pub append: Option<String>,
+    /// Prepend a line to the start of the body
+    #[clap(long)]
+    pub prepend: Option<String>,
+    /// Add a tag (can be repeated)
+    #[clap(long = "add-tag", value_name = "TAG")]
+    pub add_tags: Vec<String>,
+    /// Remove a tag (can be repeated)
+    #[clap(long = "remove-tag", value_name = "TAG")]
+    pub remove_tags: Vec<String>,
+    /// Rename a tag (can be repeated)
+    #[clap(long = "rename-tag", value_name = "OLD=NEW")]
+    pub rename_tags: Vec<String>,
+    /// Change the date of the entry (YYYY-MM-DD)
+    #[clap(long = "set-date", value_name = "DATE")]
+    pub set_date: Option<String>,
+    /// Set a metadata field (can be repeated)
+    #[clap(long = "field", value_name = "KEY=VALUE")]
+    pub fields: Vec<String>,
+    /// Remove a metadata field (can be repeated)
+    #[clap(long = "remove-field", value_name = "KEY")]
+    pub remove_fields: Vec<String>,
+    /// Change the kind of the entry, e.g. task or note
+    #[clap(long)]
+    pub kind: Option<String>,
+    /// Change the status of a task
+    #[clap(long, value_enum)]
+    pub status: Option<TaskStatus>,
+    /// Change the priority of a task
+    #[clap(long, value_enum)]
+    pub priority: Option<Priority>,
+    /// Set the due date of a task, e.g. 2024-06-01, friday or 3d; "none" removes it
+    #[clap(long)]
+    pub due: Option<String>,
+    /// Show the affected entries without saving any changes
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+/// Non-interactive changes applied to every selected entry
+#[derive(Debug, Default)]
+struct EditOps {
+    /// `Some(None)` removes the title
+    title: Option<Option<String>>,
+    body: Option<String>,
+    append: Option<String>,
+    prepend: Option<String>,
+    add_tags: Vec<Tag>,
+    remove_tags: Vec<Tag>,
+    rename_tags: Vec<(Tag, Tag)>,
+    date: Option<NaiveDate>,
+    set_fields: Vec<(String, String)>,
+    remove_fields: Vec<String>,
+    kind: Option<String>,
+    status: Option<TaskStatus>,
+    priority: Option<Priority>,
+    /// `Some(None)` removes the due date
+    due: Option<Option<NaiveDate>>,
 }
 
-pub fn execute(journal: &mut Journal, args: EditArgs) -> JotResult<()> {
-    let id = args.id;
-    match journal.get_entry(id) {
-        Some(entry) => {
-            println!("Editing entry: {}", entry.body);
-            let new_body = handle_input(&format!("Enter new content [{}]: ", entry.body));
-            let tags_str = entry
-                .tags
+impl EditOps {
+    fn from_args(args: &EditArgs, config: &Config) -> JotResult<Self> {
+        let cfg = &config.journal_cfg;
+        let date = args
+            .set_date
+            .as_deref()
+            .map(|d| {
+                utils::parse_date(d)
+                    .map_err(|e| JotError::EditError(format!("Invalid date '{}': {}", d, e)))
+            })
+            .transpose()?;
+
+        let rename_tags = args
+            .rename_tags
+            .iter()
+            .map(|r| match r.split_once('=') {
+                Some((old, new)) => Ok((parse_tag_arg(old, cfg)?, parse_tag_arg(new, cfg)?)),
+                _ => Err(JotError::EditError(format!(
+                    "Invalid tag rename '{}', expected OLD=NEW",
+                    r
+                ))),
+            })
+            .collect::<JotResult<Vec<_>>>()?;
+
+        let set_fields = args
+            .fields
+            .iter()
+            .map(|f| fields::parse_field_arg(f).map_err(JotError::EditError))
+            .collect::<JotResult<Vec<_>>>()?;
+        let remove_fields = args
+            .remove_fields
+            .iter()
+            .map(|k| {
+                fields::normalize_key(k).ok_or_else(|| JotError::EditError(fields::invalid_key(k)))
+            })
+            .collect::<JotResult<Vec<_>>>()?;
+
+        Ok(Self {
+            title: args.title.as_ref().map(|t| {
+                let t = t.trim();
+                (!t.is_empty()).then(|| t.to_string())
+            }),
+            body: args.body.clone(),
+            append: args.append.clone(),
+            prepend: args.prepend.clone(),
+            add_tags: tags::dedup_tags(
+                args.add_tags
+                    .iter()
+                    .map(|t| parse_tag_arg(t, cfg))
+                    .collect::<JotResult<Vec<_>>>()?,
+            ),
+            remove_tags: args
+                .remove_tags
                 .iter()
-                .map(|t| t.name.clone())
-                .collect::<Vec<_>>()
-                .join(" ");
-            let new_tags: Vec<Tag> = handle_input(&format!("Enter new tags [{}]: ", tags_str))
-                .split_whitespace()
-                .map(|s| Tag::new(s.to_string()))
-                .collect();
-
-            let new_entry = Entry {
-                id: entry.id,
-                body: new_body,
-                tags: new_tags,
-                date: entry.date,
-                timestamp: entry.timestamp,
-            };
-
-            journal.update_entry(new_entry);
-            storage::save_journal(journal)?;
-
-            println!("{}", "Entry updated!".green());
-
-            Ok(())
-        }
-        None => Err(JotError::EditError(format!(
+                .map(|t| parse_tag_arg(t, cfg))
+                .collect::<JotResult<Vec<_>>>()?,
+            rename_tags,
+            date,
+            set_fields,
+            remove_fields,
+            kind: args
+                .kind
+                .as_deref()
+                .map(|k| kinds::resolve_kind(k, config).map_err(JotError::EditError))
+                .transpose()?
+                .map(|(name, _)| name),
+            status: args.status,
+            priority: args.priority,
+            due: args
+                .due
+                .as_deref()
+                .map(|d| parse_due(d).map_err(JotError::EditError))
+                .transpose()?,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.body.is_none()
+            && self.append.is_none()
+            && self.prepend.is_none()
+            && self.add_tags.is_empty()
+            && self.remove_tags.is_empty()
+            && self.rename_tags.is_empty()
+            && self.date.is_none()
+            && self.set_fields.is_empty()
+            && self.remove_fields.is_empty()
+            && self.kind.is_none()
+            && self.status.is_none()
+            && self.priority.is_none()
+            && self.due.is_none()
+    }
+
+    fn apply(&self, entry: &mut Entry, cfg: &JournalConfig) {
+        if let Some(title) = &self.title {
+            entry.title = title.clone();
+        }
+        if let Some(body) = &self.body {
+            entry.body = body.clone();
+        }
+        if let Some(prepend) = &self.prepend {
+            entry.body = join_lines(prepend, &entry.body);
+        }
+        if let Some(append) = &self.append {
+            entry.body = join_lines(&entry.body, append);
+        }
+
+        for (old, new) in &self.rename_tags {
+            tags::rename_tag(entry, old, Some(new), cfg);
+        }
+        entry
+            .tags
+            .retain(|t| !self.remove_tags.contains(&tags::renormalize(t, cfg)));
+        for tag in &self.add_tags {
+            if !entry.tags.iter().any(|t| tags::renormalize(t, cfg) == *tag) {
+                entry.tags.push(tag.clone());
+            }
+        }
+
+        if let Some(date) = self.date {
+            entry.date = date;
+        }
+
+        for key in &self.remove_fields {
+            entry.fields.remove(key);
+        }
+        entry.fields.extend(self.set_fields.iter().cloned());
+
+        if let Some(kind) = &self.kind {
+            entry.kind = kind.clone();
+            entry.sync_task();
+        }
+        // Status, priority and due date only apply to tasks; `apply_ops`
+        // rejects them for other entries
+        if let Some(task) = &mut entry.task {
+            if let Some(status) = self.status {
+                task.set_status(status);
+            }
+            if let Some(priority) = self.priority {
+                task.priority = priority;
+            }
+            if let Some(due) = self.due {
+                task.due = due;
+            }
+        }
+    }
+}
+
+/// Parses a due date, where "none" or nothing means no due date
+fn parse_due(due: &str) -> Result<Option<NaiveDate>, String> {
+    let due = due.trim();
+    if due.is_empty() || due.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+
+    let today = chrono::Local::now().date_naive();
+    dates::parse_relative_date(due, today)
+        .map(Some)
+        .ok_or_else(|| format!("Invalid due date '{}': {}", due, dates::RELATIVE_DATE_HELP))
+}
+
+fn parse_tag_arg(tag: &str, cfg: &JournalConfig) -> JotResult<Tag> {
+    tags::normalize_tag(tag, cfg)
+        .ok_or_else(|| JotError::EditError(format!("Invalid tag '{}'", tag)))
+}
+
+fn join_lines(first: &str, second: &str) -> String {
+    match (first.is_empty(), second.is_empty()) {
+        (true, _) => second.to_string(),
+        (_, true) => first.to_string(),
+        _ => format!("{}\n{}", first, second),
+    }
+}
+
+/// The parts of an entry that can be changed from the editor document
+#[derive(Debug, PartialEq)]
+struct EditedEntry {
+    date: NaiveDate,
+    title: Option<String>,
+    /// `None` keeps the current kind
+    kind: Option<String>,
+    status: Option<TaskStatus>,
+    priority: Option<Priority>,
+    /// `None` keeps the current due date, `Some(None)` removes it
+    due: Option<Option<NaiveDate>>,
+    tags: Vec<Tag>,
+    fields: BTreeMap<String, String>,
+    body: String,
+}
+
+pub fn execute(journal: &mut Journal, args: EditArgs, config: &Config) -> JotResult<()> {
+    let cfg = &config.journal_cfg;
+    let ops = EditOps::from_args(&args, config)?;
+    let ids = select_entries(journal, &args, cfg)?;
+
+    if ops.is_empty() && !args.dry_run {
+        match ids.as_slice() {
+            [id] => edit_in_editor(journal, *id, config),
+            _ => Err(JotError::EditError(
+                "Interactive editing takes exactly one entry; pass editing options such as \
+                 --title, --append or --add-tag to edit several (see --help)"
+                    .to_string(),
+            )),
+        }
+    } else {
+        apply_ops(journal, &ids, &ops, args.dry_run, cfg)
+    }
+}
+
+/// Collects the IDs given on the command line and those matched by `--match` or `--where`
+fn select_entries(
+    journal: &Journal,
+    args: &EditArgs,
+    cfg: &JournalConfig,
+) -> JotResult<Vec<usize>> {
+    if let Some(id) = args.ids.iter().find(|id| journal.get_entry(**id).is_none()) {
+        return Err(JotError::EditError(format!(
             "Entry with ID {} not found",
             id
-        ))),
+        )));
+    }
+
+    let mut ids = args.ids.clone();
+    if let Some(text) = &args.matching {
+        let filter = EntryFilter {
+            text: vec![text.clone()],
+            ..EntryFilter::default()
+        };
+        ids.extend(
+            journal
+                .get_entries()
+                .iter()
+                .filter(|e| filter.matches(e))
+                .map(|e| e.id),
+        );
+    }
+    if let Some(query) = &args.where_query {
+        let filter = EntryFilter::parse(query, cfg)?;
+        ids.extend(
+            journal
+                .get_entries()
+                .iter()
+                .filter(|e| filter.matches(e))
+                .map(|e| e.id),
+        );
+    }
+
+    ids.sort_unstable();
+    ids.dedup();
+
+    if ids.is_empty() {
+        return Err(JotError::EditError("No entries to edit".to_string()));
+    }
+
+    Ok(ids)
+}
+
+/// Applies the operations to every selected entry and saves the journal once
+fn apply_ops(
+    journal: &mut Journal,
+    ids: &[usize],
+    ops: &EditOps,
+    dry_run: bool,
+    cfg: &JournalConfig,
+) -> JotResult<()> {
+    let edited = ids
+        .iter()
+        .filter_map(|id| journal.get_entry(*id))
+        .map(|entry| {
+            let mut new_entry = entry.clone();
+            ops.apply(&mut new_entry, cfg);
+            (entry, new_entry)
+        })
+        .collect::<Vec<_>>();
+
+    if ops.status.is_some() || ops.priority.is_some() || ops.due.is_some() {
+        let not_tasks = edited
+            .iter()
+            .filter(|(_, new)| new.task.is_none())
+            .map(|(old, _)| old.id.to_string())
+            .collect::<Vec<_>>();
+        if !not_tasks.is_empty() {
+            return Err(JotError::CommandError(format!(
+                "--status, --priority and --due only apply to tasks, but these entries \
+                 are not tasks: {}; add --kind task to make them tasks",
+                not_tasks.join(", ")
+            )));
+        }
     }
+
+    let updated = edited
+        .into_iter()
+        .filter(|(old, new)| *old != new)
+        .collect::<Vec<_>>();
+
+    if updated.is_empty() {
+        println!(
+            "{}",
+            "No changes to apply, journal left untouched.".yellow()
+        );
+        return Ok(());
+    }
+
+    if dry_run {
+        for (old, new) in &updated {
+            print_change(old, new);
+        }
+        println!(
+            "{}",
+            format!(
+                "{} would be updated (dry run, nothing saved)",
+                utils::count_entries(updated.len())
+            )
+            .yellow()
+        );
+        return Ok(());
+    }
+
+    let updated = updated.into_iter().map(|(_, new)| new).collect::<Vec<_>>();
+    let count = updated.len();
+    for entry in updated {
+        journal.update_entry(entry);
+    }
+    storage::save_journal(journal)?;
+
+    println!(
+        "{}",
+        format!("{} updated!", utils::count_entries(count)).green()
+    );
+
+    Ok(())
 }
 
-fn handle_input(prompt: &str) -> String {
-    utils::get_input(prompt)
+fn print_change(old: &Entry, new: &Entry) {
+    let format_tags = |entry: &Entry| {
+        entry
+            .tags
+            .iter()
+            .map(|t| format!("#{}", t.name))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    println!(
+        "[{:>3}] {} {}",
+        old.id,
+        new.date.format("%Y-%m-%d").to_string().bright_blue(),
+        old.display_title()
+    );
+    if old.title != new.title {
+        println!(
+            "      title: {} -> {}",
+            old.title.as_deref().unwrap_or_default().red(),
+            new.title.as_deref().unwrap_or_default().green()
+        );
+    }
+    if old.tags != new.tags {
+        println!(
+            "      tags: {} -> {}",
+            format_tags(old).red(),
+            format_tags(new).green()
+        );
+    }
+    if old.body != new.body {
+        println!("      body changed");
+    }
+    if old.date != new.date {
+        println!("      date: {} -> {}", old.date, new.date);
+    }
+    if old.kind != new.kind {
+        println!("      kind: {} -> {}", old.kind.red(), new.kind.green());
+    }
+    if old.task != new.task {
+        let format_task = |entry: &Entry| {
+            entry
+                .task
+                .as_ref()
+                .map(|t| match t.due {
+                    Some(due) => format!("{}, {} priority, due {}", t.status, t.priority, due),
+                    None => format!("{}, {} priority", t.status, t.priority),
+                })
+                .unwrap_or_default()
+        };
+        println!(
+            "      task: {} -> {}",
+            format_task(old).red(),
+            format_task(new).green()
+        );
+    }
+    if old.fields != new.fields {
+        println!(
+            "      fields: {} -> {}",
+            fields::format_fields(&old.fields).red(),
+            fields::format_fields(&new.fields).green()
+        );
+    }
+}
+
+fn edit_in_editor(journal: &mut Journal, id: usize, config: &Config) -> JotResult<()> {
+    let entry = journal
+        .get_entry(id)
+        .cloned()
+        .ok_or_else(|| JotError::EditError(format!("Entry with ID {} not found", id)))?;
+
+    let document = render_document(&entry);
+    let edited = Editor::new()
+        .extension(".md")
+        .edit(&document)
+        .map_err(|e| JotError::EditError(format!("Failed to open editor: {}", e)))?;
+
+    let edited = match edited {
+        Some(text) if text.trim() != document.trim() => text,
+        _ => {
+            println!("{}", "No changes made, entry left untouched.".yellow());
+            return Ok(());
+        }
+    };
+
+    if edited.trim().is_empty() {
+        println!("{}", "Edit aborted: the entry was emptied.".yellow());
+        return Ok(());
+    }
+
+    let edited = parse_document(&edited, config)?;
+    if edited.body.is_empty() {
+        println!("{}", "Edit aborted: the entry body is empty.".yellow());
+        return Ok(());
+    }
+
+    journal.update_entry(apply_document(entry, edited)?);
+    storage::save_journal(journal)?;
+
+    println!("{}", "Entry updated!".green());
+
+    Ok(())
+}
+
+/// Applies an edited document to the entry it was rendered from, rejecting
+/// task state on an entry that isn't a task
+fn apply_document(entry: Entry, edited: EditedEntry) -> JotResult<Entry> {
+    let id = entry.id;
+    let mut updated = Entry {
+        date: edited.date,
+        title: edited.title,
+        kind: edited.kind.unwrap_or(entry.kind.clone()),
+        tags: edited.tags,
+        fields: edited.fields,
+        body: edited.body,
+        ..entry
+    };
+    updated.sync_task();
+    match &mut updated.task {
+        Some(task) => {
+            if let Some(status) = edited.status {
+                task.set_status(status);
+            }
+            if let Some(priority) = edited.priority {
+                task.priority = priority;
+            }
+            if let Some(due) = edited.due {
+                task.due = due;
+            }
+        }
+        None if edited.status.is_some() || edited.priority.is_some() || edited.due.is_some() => {
+            return Err(JotError::CommandError(format!(
+                "status, priority and due only apply to tasks, but entry {} is not a task; \
+                 set kind: task to make it a task",
+                id
+            )));
+        }
+        None => {}
+    }
+    Ok(updated)
+}
+
+/// Renders an entry as a document with a front-matter block holding its date,
+/// kind, task state, tags and one line per metadata field
+fn render_document(entry: &Entry) -> String {
+    let tags = entry
+        .tags
+        .iter()
+        .map(|t| format!("#{}", t.name))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let task = entry
+        .task
+        .as_ref()
+        .map(|t| {
+            format!(
+                "status: {}\npriority: {}\ndue: {}\n",
+                t.status,
+                t.priority,
+                t.due.map(|d| d.to_string()).unwrap_or_default()
+            )
+        })
+        .unwrap_or_default();
+    let fields = entry
+        .fields
+        .iter()
+        .map(|(key, value)| format!("{}: {}\n", key, value))
+        .collect::<String>();
+
+    format!(
+        "{delim}\ntitle: {}\ndate: {}\nkind: {}\n{}tags: {}\n{}{delim}\n\n{}\n",
+        entry.title.as_deref().unwrap_or_default(),
+        entry.date.format("%Y-%m-%d"),
+        entry.kind,
+        task,
+        tags,
+        fields,
+        entry.body,
+        delim = FRONT_MATTER_DELIMITER
+    )
+}
+
+/// Parses a document produced by `render_document` back into entry fields
+fn parse_document(document: &str, config: &Config) -> JotResult<EditedEntry> {
+    let mut lines = document.lines();
+    if lines.next().map(str::trim) != Some(FRONT_MATTER_DELIMITER) {
+        return Err(JotError::EditError(
+            "Missing front-matter block at the top of the entry".to_string(),
+        ));
+    }
+
+    let mut date = None;
+    let mut title = None;
+    let mut kind = None;
+    let mut status = None;
+    let mut priority = None;
+    let mut due = None;
+    let mut tags = Vec::new();
+    let mut entry_fields = BTreeMap::new();
+    let mut closed = false;
+
+    for line in lines.by_ref() {
+        let line = line.trim();
+        if line == FRONT_MATTER_DELIMITER {
+            closed = true;
+            break;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| JotError::EditError(format!("Invalid front-matter line '{}'", line)))?;
+
+        match key.trim() {
+            "date" => {
+                let parsed = utils::parse_date(value).map_err(|e| {
+                    JotError::EditError(format!("Invalid date '{}': {}", value.trim(), e))
+                })?;
+                date = Some(parsed);
+            }
+            "title" => title = Some(value.trim().to_string()).filter(|t| !t.is_empty()),
+            "kind" => {
+                let (name, _) = kinds::resolve_kind(value, config).map_err(JotError::EditError)?;
+                kind = Some(name);
+            }
+            "status" => {
+                status = Some(TaskStatus::from_str(value.trim(), true).map_err(|_| {
+                    JotError::EditError(format!("Invalid task status '{}'", value.trim()))
+                })?)
+            }
+            "priority" => {
+                priority = Some(Priority::from_str(value.trim(), true).map_err(|_| {
+                    JotError::EditError(format!("Invalid priority '{}'", value.trim()))
+                })?)
+            }
+            "due" => due = Some(parse_due(value).map_err(JotError::EditError)?),
+            "tags" => tags = tags::parse_tag_list(value, &config.journal_cfg),
+            other => {
+                let key = fields::normalize_key(other).ok_or_else(|| {
+                    JotError::EditError(format!("Unknown front-matter field '{}'", other))
+                })?;
+                // Emptying a field's value removes it
+                if !value.trim().is_empty() {
+                    entry_fields.insert(key, value.trim().to_string());
+                }
+            }
+        }
+    }
+
+    if !closed {
+        return Err(JotError::EditError(
+            "Front-matter block is not closed with '---'".to_string(),
+        ));
+    }
+
+    let date =
+        date.ok_or_else(|| JotError::EditError("Front-matter is missing a date".to_string()))?;
+    let body = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+
+    Ok(EditedEntry {
+        date,
+        title,
+        kind,
+        status,
+        priority,
+        due,
+        tags,
+        fields: entry_fields,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::TASK_KIND;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_document_round_trip() {
+        let mut entry = Entry::new(
+            3,
+            "First line\nsecond line".to_string(),
+            vec![Tag::new("work".to_string()), Tag::new("ops".to_string())],
+        );
+        entry.fields.insert("hours".to_string(), "2.5".to_string());
+
+        let parsed = parse_document(&render_document(&entry), &Config::default()).unwrap();
+        assert_eq!(parsed.date, entry.date);
+        assert_eq!(parsed.title, None);
+        assert_eq!(parsed.tags, entry.tags);
+        assert_eq!(parsed.fields, entry.fields);
+        assert_eq!(parsed.kind.as_deref(), Some("note"));
+        assert_eq!(parsed.status, None);
+
+        entry.kind = "task".to_string();
+        entry.sync_task();
+        let parsed = parse_document(&render_document(&entry), &Config::default()).unwrap();
+        assert_eq!(parsed.status, Some(TaskStatus::Open));
+        assert_eq!(parsed.priority, Some(Priority::Medium));
+        assert_eq!(parsed.due, Some(None));
+
+        entry.task.as_mut().unwrap().due = NaiveDate::from_ymd_opt(2024, 6, 1);
+        let parsed = parse_document(&render_document(&entry), &Config::default()).unwrap();
+        assert_eq!(parsed.due, Some(NaiveDate::from_ymd_opt(2024, 6, 1)));
+        assert_eq!(parsed.body, entry.body);
+    }
+
+    #[test]
+    fn test_parse_document_changes() {
+        let document =
+            "---\ntitle: A title\ndate: 2024-05-01\ntags: #a b\n---\n\nNew body\n\nwith paragraphs\n";
+        let parsed = parse_document(document, &Config::default()).unwrap();
+
+        assert_eq!(parsed.date, NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+        assert_eq!(parsed.title.as_deref(), Some("A title"));
+        assert_eq!(
+            parsed.tags,
+            vec![Tag::new("a".to_string()), Tag::new("b".to_string())]
+        );
+        assert_eq!(parsed.body, "New body\n\nwith paragraphs");
+    }
+
+    #[test]
+    fn test_edit_ops_apply() {
+        let mut entry = Entry::new(
+            0,
+            "body".to_string(),
+            vec![Tag::new("a".to_string()), Tag::new("b".to_string())],
+        );
+        let ops = EditOps {
+            append: Some("after".to_string()),
+            prepend: Some("before".to_string()),
+            add_tags: vec![Tag::new("c".to_string()), Tag::new("a".to_string())],
+            remove_tags: vec![Tag::new("b".to_string())],
+            date: NaiveDate::from_ymd_opt(2024, 1, 2),
+            ..Default::default()
+        };
+
+        ops.apply(&mut entry, &JournalConfig::default());
+        assert_eq!(entry.body, "before\nbody\nafter");
+        assert_eq!(
+            entry.tags,
+            vec![Tag::new("a".to_string()), Tag::new("c".to_string())]
+        );
+        assert_eq!(entry.date, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+
+        let rename = EditOps {
+            rename_tags: vec![
+                (Tag::new("a".to_string()), Tag::new("x".to_string())),
+                (Tag::new("c".to_string()), Tag::new("x".to_string())),
+            ],
+            ..Default::default()
+        };
+        rename.apply(&mut entry, &JournalConfig::default());
+        assert_eq!(entry.tags, vec![Tag::new("x".to_string())]);
+
+        let replace = EditOps {
+            body: Some("replaced".to_string()),
+            ..Default::default()
+        };
+        replace.apply(&mut entry, &JournalConfig::default());
+        assert_eq!(entry.body, "replaced");
+
+        entry.fields.insert("mood".to_string(), "3".to_string());
+        let fields = EditOps {
+            set_fields: vec![("hours".to_string(), "2".to_string())],
+            remove_fields: vec!["mood".to_string()],
+            ..Default::default()
+        };
+        fields.apply(&mut entry, &JournalConfig::default());
+        assert_eq!(
+            entry.fields,
+            BTreeMap::from([("hours".to_string(), "2".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_task_ops_on_notes() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut journal = Journal::new(temp_dir.path().join("journal.json"));
+        journal.add_entry(Entry::new(0, "note".to_string(), vec![]));
+        journal.add_entry(Entry::new(0, "other note".to_string(), vec![]));
+        let cfg = JournalConfig::default();
+        let ops = EditOps {
+            status: Some(TaskStatus::Done),
+            ..Default::default()
+        };
+
+        match apply_ops(&mut journal, &[0, 1], &ops, true, &cfg) {
+            Err(JotError::CommandError(message)) => assert!(message.contains("tasks: 0, 1;")),
+            _ => panic!("expected an error for entries that aren't tasks"),
+        }
+
+        // Turning them into tasks in the same edit is fine
+        let ops = EditOps {
+            kind: Some(TASK_KIND.to_string()),
+            ..ops
+        };
+        assert!(apply_ops(&mut journal, &[0, 1], &ops, true, &cfg).is_ok());
+    }
+
+    #[test]
+    fn test_apply_document_task_state_on_notes() {
+        let note = Entry::new(4, "note".to_string(), vec![]);
+        let document = "---\ndate: 2024-05-01\nstatus: done\n---\n\nnote\n";
+        let edited = parse_document(document, &Config::default()).unwrap();
+        match apply_document(note.clone(), edited) {
+            Err(JotError::CommandError(message)) => assert!(message.contains("entry 4")),
+            _ => panic!("expected an error for task state on a note"),
+        }
+
+        let document = "---\ndate: 2024-05-01\nkind: task\nstatus: done\n---\n\nnote\n";
+        let edited = parse_document(document, &Config::default()).unwrap();
+        let updated = apply_document(note, edited).unwrap();
+        assert_eq!(updated.task.map(|t| t.status), Some(TaskStatus::Done));
+    }
+
+    #[test]
+    fn test_parse_document_errors() {
+        let cfg = Config::default();
+        assert!(parse_document("no front matter", &cfg).is_err());
+        assert!(parse_document("---\ndate: 2024-05-01\nbody", &cfg).is_err());
+        assert!(parse_document("---\ndate: yesterday\n---\nbody", &cfg).is_err());
+        assert!(parse_document("---\ntags: a\n---\nbody", &cfg).is_err());
+        assert!(parse_document("---\ndate: 2024-05-01\n2x: 1\n---\nbody", &cfg).is_err());
+        assert!(parse_document("---\ndate: 2024-05-01\nkind: x\n---\nbody", &cfg).is_err());
+        assert!(parse_document("---\ndate: 2024-05-01\nstatus: x\n---\nbody", &cfg).is_err());
+    }
 }
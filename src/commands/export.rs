@@ -1,9 +1,10 @@
 use crate::{
     error::{JotError, JotResult},
     storage::{config::Config, Entry, Journal},
+    utils::{filter::EntryFilter, query::Query},
 };
 use chrono::Local;
-use std::{fs, path::Path};
+use std::{collections::BTreeSet, fs, path::Path};
 
 #[derive(clap::Args, Clone)]
 pub struct ExportArgs {
@@ -13,6 +14,12 @@ pub struct ExportArgs {
     #[clap(short, long)]
     /// Open the exported file with the default program
     pub open: bool,
+    /// Only export entries of these kinds, e.g. task,log
+    #[clap(short, long = "kind", value_name = "KIND", value_delimiter = ',')]
+    pub kinds: Vec<String>,
+    /// Only export entries matching a filter query, e.g. "tag:work AND date:>=2024-01-01"
+    #[clap(short, long = "where", value_name = "QUERY")]
+    pub where_query: Option<String>,
 }
 
 #[derive(clap::ValueEnum, Clone, Copy)]
@@ -23,7 +30,23 @@ pub enum ExportFormat {
 }
 
 pub fn execute(journal: &mut Journal, args: ExportArgs, config: &Config) -> JotResult<()> {
-    let entries = journal.get_entries();
+    let filter = EntryFilter {
+        kinds: args.kinds.iter().map(|k| k.trim().to_lowercase()).collect(),
+        query: args
+            .where_query
+            .as_deref()
+            .map(|q| Query::parse(q, &config.journal_cfg))
+            .transpose()?
+            .flatten(),
+        tag_cfg: Some(config.journal_cfg.clone()),
+        ..EntryFilter::default()
+    };
+    let entries = journal
+        .get_entries()
+        .iter()
+        .filter(|e| filter.matches(e))
+        .cloned()
+        .collect::<Vec<_>>();
     let export_dir = journal
         .path()
         .parent()
@@ -35,9 +58,9 @@ pub fn execute(journal: &mut Journal, args: ExportArgs, config: &Config) -> JotR
     let filename = generate_filename(args.format, timestamp);
 
     let content = match args.format {
-        ExportFormat::Json => export_to_json(entries)?,
-        ExportFormat::Csv => export_to_csv(entries),
-        ExportFormat::Plain => export_to_plain(entries),
+        ExportFormat::Json => export_to_json(&entries)?,
+        ExportFormat::Csv => export_to_csv(&entries),
+        ExportFormat::Plain => export_to_plain(&entries),
     };
 
     let export_path = export_dir.join(filename);
@@ -62,12 +85,45 @@ fn generate_filename(format: ExportFormat, timestamp: impl std::fmt::Display) ->
     }
 }
 
+/// Entries with their effective title filled in, so exports never lack one
+fn with_titles(entries: &[Entry]) -> Vec<Entry> {
+    entries
+        .iter()
+        .map(|e| Entry {
+            title: Some(e.display_title().to_string()),
+            ..e.clone()
+        })
+        .collect()
+}
+
 fn export_to_json(entries: &[Entry]) -> JotResult<String> {
-    serde_json::to_string_pretty(&entries).map_err(JotError::SerdeError)
+    serde_json::to_string_pretty(&with_titles(entries)).map_err(JotError::SerdeError)
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
 }
 
+/// Exports one row per entry; every metadata field used in the journal gets
+/// its own column after the fixed ones, left empty for entries without it
 fn export_to_csv(entries: &[Entry]) -> String {
-    let mut csv = String::from("date,title,body,tags\n");
+    let field_keys = entries
+        .iter()
+        .flat_map(|e| e.fields.keys())
+        .collect::<BTreeSet<_>>();
+
+    let mut csv = String::from("date,title,body,tags,kind");
+    for key in &field_keys {
+        csv.push(',');
+        csv.push_str(&escape_csv(key));
+    }
+    csv.push('\n');
+
     for entry in entries {
         let tags = entry
             .tags
@@ -76,11 +132,24 @@ fn export_to_csv(entries: &[Entry]) -> String {
             .collect::<Vec<_>>()
             .join(",");
         csv.push_str(&format!(
-            "{},{},{}\n",
+            "{},{},{},{},{}",
             entry.date,
-            entry.body.replace("\n", " "),
-            tags
+            escape_csv(entry.display_title()),
+            escape_csv(&entry.body),
+            escape_csv(&tags),
+            escape_csv(&entry.kind)
         ));
+        for key in &field_keys {
+            csv.push(',');
+            csv.push_str(&escape_csv(
+                entry
+                    .fields
+                    .get(*key)
+                    .map(String::as_str)
+                    .unwrap_or_default(),
+            ));
+        }
+        csv.push('\n');
     }
     csv
 }
@@ -88,7 +157,9 @@ fn export_to_csv(entries: &[Entry]) -> String {
 fn export_to_plain(entries: &[Entry]) -> String {
     let mut text = String::new();
     for entry in entries {
+        text.push_str(&format!("Title: {}\n", entry.display_title()));
         text.push_str(&format!("Date: {}\n", entry.date));
+        text.push_str(&format!("Kind: {}\n", entry.kind));
         if !entry.tags.is_empty() {
             let tags_str = entry
                 .tags
@@ -98,6 +169,9 @@ fn export_to_plain(entries: &[Entry]) -> String {
                 .join(", ");
             text.push_str(&format!("Tags: {}\n", tags_str));
         }
+        for (key, value) in &entry.fields {
+            text.push_str(&format!("{}: {}\n", key, value));
+        }
         text.push_str(&format!("\n{}\n", entry.body));
         text.push_str("\n---\n\n");
     }
@@ -118,9 +192,9 @@ fn open_exported_file(export_path: &Path) -> JotResult<()> {
         }
     };
 
-    let path = export_path.to_str().ok_or_else(|| {
-        JotError::ExportError("Export path contains invalid Unicode".to_string())
-    })?;
+    let path = export_path
+        .to_str()
+        .ok_or_else(|| JotError::ExportError("Export path contains invalid Unicode".to_string()))?;
 
     let status = std::process::Command::new(command).arg(path).status()?;
 
@@ -133,3 +207,57 @@ fn open_exported_file(export_path: &Path) -> JotResult<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Tag;
+
+    #[test]
+    fn test_export_to_csv_columns() {
+        let mut entry = Entry::new(
+            0,
+            "Line one, with comma\nline \"two\"".to_string(),
+            vec![Tag::new("a".to_string()), Tag::new("b".to_string())],
+        );
+        let csv = export_to_csv(&[entry.clone()]);
+        assert_eq!(
+            csv,
+            format!(
+                "date,title,body,tags,kind\n{},\"Line one, with comma\",\"Line one, with comma\nline \"\"two\"\"\",\"a,b\",note\n",
+                entry.date
+            )
+        );
+
+        entry.title = Some("Title".to_string());
+        entry.tags.clear();
+        entry.body = "body".to_string();
+        assert!(export_to_csv(&[entry]).ends_with(",Title,body,,note\n"));
+    }
+
+    #[test]
+    fn test_export_fields() {
+        let mut first = Entry::new(0, "a".to_string(), vec![]);
+        first.fields.insert("hours".to_string(), "2.5".to_string());
+        let mut second = Entry::new(1, "b".to_string(), vec![]);
+        second
+            .fields
+            .insert("project".to_string(), "apollo, gemini".to_string());
+
+        let csv = export_to_csv(&[first.clone(), second]);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "date,title,body,tags,kind,hours,project");
+        assert!(lines[1].ends_with(",a,a,,note,2.5,"));
+        assert!(lines[2].ends_with(",b,b,,note,,\"apollo, gemini\""));
+
+        let json = export_to_json(&[first]).unwrap();
+        assert!(json.contains("\"hours\": \"2.5\""));
+    }
+
+    #[test]
+    fn test_export_to_json_includes_title() {
+        let entry = Entry::new(0, "First line\nmore".to_string(), vec![]);
+        let json = export_to_json(&[entry]).unwrap();
+        assert!(json.contains("\"title\": \"First line\""));
+    }
+}
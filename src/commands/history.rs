@@ -0,0 +1,127 @@
+use colored::Colorize;
+
+use crate::{
+    error::{JotError, JotResult},
+    storage::{
+        self,
+        history::{self, Change},
+        Journal,
+    },
+};
+
+#[derive(clap::Args, Clone)]
+pub struct UndoArgs {
+    /// Number of operations to undo
+    #[clap(default_value_t = 1)]
+    pub count: usize,
+}
+
+#[derive(clap::Args, Clone)]
+pub struct LogArgs {
+    /// Number of operations to show
+    #[clap(short = 'n', long, default_value_t = 10)]
+    pub limit: usize,
+    /// List every changed entry of each operation
+    #[clap(short, long)]
+    pub verbose: bool,
+}
+
+/// Reverts the last `count` operations, most recent first
+pub fn undo(journal: &mut Journal, args: UndoArgs) -> JotResult<()> {
+    let mut history = history::load_history(journal)?;
+    if history.done.is_empty() {
+        return Err(JotError::CommandError("Nothing to undo".to_string()));
+    }
+
+    let count = args.count.min(history.done.len());
+    for _ in 0..count {
+        let Some(operation) = history.done.pop() else {
+            break;
+        };
+        operation.undo(journal)?;
+        println!(
+            "Undid {} ({})",
+            format!("xlog {}", operation.command).bold(),
+            operation.summary()
+        );
+        history.undone.push(operation);
+    }
+
+    storage::save_journal(journal)?;
+    history::save_history(&history)
+}
+
+/// Applies the last `count` undone operations again
+pub fn redo(journal: &mut Journal, args: UndoArgs) -> JotResult<()> {
+    let mut history = history::load_history(journal)?;
+    if history.undone.is_empty() {
+        return Err(JotError::CommandError("Nothing to redo".to_string()));
+    }
+
+    let count = args.count.min(history.undone.len());
+    for _ in 0..count {
+        let Some(operation) = history.undone.pop() else {
+            break;
+        };
+        operation.redo(journal)?;
+        println!(
+            "Redid {} ({})",
+            format!("xlog {}", operation.command).bold(),
+            operation.summary()
+        );
+        history.done.push(operation);
+    }
+
+    storage::save_journal(journal)?;
+    history::save_history(&history)
+}
+
+/// Lists the most recent operations, newest first
+pub fn log(journal: &Journal, args: LogArgs) -> JotResult<()> {
+    let history = history::load_history(journal)?;
+    if history.done.is_empty() && history.undone.is_empty() {
+        println!("No operations recorded.");
+        return Ok(());
+    }
+
+    let undone = history.undone.iter().map(|op| (op, true));
+    let done = history.done.iter().rev().map(|op| (op, false));
+
+    for (operation, is_undone) in undone.chain(done).take(args.limit) {
+        let line = format!(
+            "{} {:<10} xlog {}",
+            operation
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+                .bright_blue(),
+            operation.summary(),
+            operation.command
+        );
+
+        if is_undone {
+            println!("{} {}", line.dimmed(), "(undone)".yellow());
+        } else {
+            println!("{}", line);
+        }
+
+        if args.verbose {
+            for change in &operation.changes {
+                let (sign, entry) = match change {
+                    Change::Added(entry) => ("+".green(), entry),
+                    Change::Removed(entry) => ("-".red(), entry),
+                    Change::Updated { after, .. } => ("~".yellow(), after),
+                };
+                println!(
+                    "    {} [{:>3}] {}",
+                    sign,
+                    entry.id,
+                    entry.body.lines().next().unwrap_or_default()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -53,12 +53,21 @@ pub fn execute(args: InitArgs) -> JotResult<()> {
         .interact()
         .map_err(|e| JotError::_InitError(format!("Failed to get tag preference: {}", e)))?;
 
+    let lowercase_tags = Confirm::new()
+        .with_prompt("Treat tags case-insensitively (#Work is the same as #work)?")
+        .default(true)
+        .interact()
+        .map_err(|e| JotError::_InitError(format!("Failed to get tag case preference: {}", e)))?;
+
     let new_config = Config {
         journal_cfg: JournalConfig {
             show_time,
             body_tags: tag_selection == 0,
             export_dir,
+            lowercase_tags,
+            ..JournalConfig::default()
         },
+        ..Config::default()
     };
 
     storage::init_journal(&new_config)?;
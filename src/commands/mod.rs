@@ -1,8 +1,18 @@
 pub mod add;
+pub mod agenda;
 pub mod backup;
+pub mod checklist;
+pub mod dupes;
 pub mod edit;
 pub mod export;
+pub mod history;
 pub mod init;
+pub mod reindex;
+pub mod related;
 pub mod remove;
+pub mod retag;
 pub mod search;
+pub mod tags;
+pub mod task;
 pub mod view;
+pub mod views;
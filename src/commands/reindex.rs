@@ -0,0 +1,21 @@
+use colored::Colorize;
+
+use crate::{
+    error::JotResult,
+    storage::{index, Journal},
+};
+
+/// Rebuilds the search index from scratch
+pub fn execute(journal: &Journal) -> JotResult<()> {
+    let index = index::rebuild_index(journal)?;
+    println!(
+        "{}",
+        format!(
+            "Indexed {} entries ({} distinct words)",
+            index.len(),
+            index.word_count()
+        )
+        .green()
+    );
+    Ok(())
+}
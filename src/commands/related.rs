@@ -0,0 +1,46 @@
+use colored::Colorize;
+
+use crate::{
+    error::{JotError, JotResult},
+    storage::{config::Config, Journal},
+    utils::{self, similarity},
+};
+
+#[derive(clap::Args, Clone)]
+pub struct RelatedArgs {
+    /// ID of the entry to find related entries for
+    pub id: usize,
+    /// Show at most this many entries
+    #[clap(short, long, default_value_t = 5)]
+    pub limit: usize,
+}
+
+/// Lists the entries sharing the most text and tags with an entry
+pub fn execute(journal: &Journal, args: RelatedArgs, config: &Config) -> JotResult<()> {
+    let entry = journal
+        .get_entry(args.id)
+        .ok_or_else(|| JotError::CommandError(format!("Entry with ID {} not found", args.id)))?;
+
+    let related = similarity::related(journal.get_entries(), entry);
+    if related.is_empty() {
+        println!("No related entries found.");
+        return Ok(());
+    }
+
+    println!("Entries related to #{}:", entry.id);
+    for (other, score) in related.into_iter().take(args.limit) {
+        let shared = other
+            .tags
+            .iter()
+            .filter(|t| entry.tags.contains(t))
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>();
+        let mut details = format!("score {:.2}", score);
+        if !shared.is_empty() {
+            details.push_str(&format!(", shared tags: {}", shared.join(" ")));
+        }
+        println!("{}", details.dimmed());
+        println!("{}", utils::format_entry(other, config));
+    }
+    Ok(())
+}
@@ -1,34 +1,42 @@
+use chrono::NaiveDate;
+use colored::Colorize;
+use dialoguer::Confirm;
+
 use crate::error::{JotError, JotResult};
-use crate::storage::{self, Journal};
-use crate::utils;
+use crate::storage::{self, config::Config, Entry, Journal};
+use crate::utils::{self, filter::EntryFilter, query::Query, tags, TagMatch};
 
 #[derive(clap::Args, Clone, Debug)]
 pub struct RemoveArgs {
+    /// ID of the entry to remove
     pub id: Option<usize>,
+    /// Remove the entries with IDs in this range, e.g. 3..7
     #[clap(short, long)]
     pub range: Option<String>,
+    /// Only remove entries from this date on
     #[clap(short, long)]
     pub from: Option<String>,
+    /// Only remove entries up to this date
     #[clap(short, long)]
     pub to: Option<String>,
+    /// Only remove entries with all of these tags
+    #[clap(long, value_delimiter = ' ', num_args = 1)]
+    pub tags: Vec<String>,
+    /// Only remove entries whose body contains this text (case-insensitive)
+    #[clap(short, long = "match", value_name = "TEXT")]
+    pub matching: Option<String>,
+    /// Only remove entries matching a filter query, e.g. "tag:draft AND date:<2024-01-01"
+    #[clap(short, long = "where", value_name = "QUERY")]
+    pub where_query: Option<String>,
+    /// List the entries that would be removed without removing them
+    #[clap(long)]
+    pub dry_run: bool,
+    /// Don't ask for confirmation when removing several entries
+    #[clap(short, long)]
+    pub yes: bool,
 }
 
-fn remove_entry(journal: &mut Journal, id: usize) -> JotResult<()> {
-    if let Some(_) = journal.remove_entry(id) {
-        storage::save_journal(journal)
-            .map_err(|e| JotError::RemoveError(e.to_string()))
-            .map(|_| {
-                println!("Entry {} removed", id);
-            })
-    } else {
-        Err(JotError::RemoveError(format!(
-            "Entry with ID {} not found",
-            id
-        )))
-    }
-}
-
-fn remove_entries_by_range(journal: &mut Journal, range: &str) -> JotResult<Vec<usize>> {
+fn parse_range(range: &str) -> JotResult<(usize, usize)> {
     let range_parts: Vec<&str> = range.split("..").collect();
     if range_parts.len() != 2 {
         return Err(JotError::RemoveError(
@@ -43,70 +51,145 @@ fn remove_entries_by_range(journal: &mut Journal, range: &str) -> JotResult<Vec<
         .parse::<usize>()
         .map_err(|_| JotError::RemoveError("Invalid range end".to_string()))?;
 
-    Ok((start..=end).collect())
+    if start > end {
+        return Err(JotError::RemoveError(format!(
+            "Invalid range {}: start is after end",
+            range
+        )));
+    }
+
+    Ok((start, end))
 }
 
-fn remove_entries_by_date_range(journal: &Journal, from: &str, to: &str) -> JotResult<Vec<usize>> {
-    let from_date = utils::parse_date(from);
-    let to_date = utils::parse_date(to);
-    Ok(journal
-        .get_entries()
-        .iter()
-        .filter(|e| e.date >= from_date && e.date <= to_date)
-        .map(|e| e.id)
-        .collect())
+fn parse_remove_date(date: &str) -> JotResult<NaiveDate> {
+    utils::parse_date(date)
+        .map_err(|e| JotError::RemoveError(format!("Invalid date '{}': {}", date, e)))
 }
 
-fn remove_entries_from_date(journal: &Journal, from: &str) -> JotResult<Vec<usize>> {
-    let from_date = utils::parse_date(from);
-    Ok(journal
-        .get_entries()
+fn build_filter(args: &RemoveArgs, config: &Config) -> JotResult<Option<EntryFilter>> {
+    if args.from.is_none()
+        && args.to.is_none()
+        && args.tags.is_empty()
+        && args.matching.is_none()
+        && args.where_query.is_none()
+    {
+        return Ok(None);
+    }
+
+    let tags = args
+        .tags
         .iter()
-        .filter(|e| e.date >= from_date)
-        .map(|e| e.id)
-        .collect())
+        .map(|t| {
+            tags::normalize_tag(t, &config.journal_cfg)
+                .ok_or_else(|| JotError::RemoveError(format!("Invalid tag '{}'", t)))
+        })
+        .collect::<JotResult<Vec<_>>>()?;
+
+    Ok(Some(EntryFilter {
+        tags,
+        match_type: TagMatch::All,
+        from: args.from.as_deref().map(parse_remove_date).transpose()?,
+        to: args.to.as_deref().map(parse_remove_date).transpose()?,
+        text: args.matching.iter().cloned().collect(),
+        query: args
+            .where_query
+            .as_deref()
+            .map(|q| Query::parse(q, &config.journal_cfg))
+            .transpose()?
+            .flatten(),
+        tag_cfg: Some(config.journal_cfg.clone()),
+        ..EntryFilter::default()
+    }))
 }
 
-fn remove_entries_to_date(journal: &Journal, to: &str) -> JotResult<Vec<usize>> {
-    let to_date = utils::parse_date(to);
+/// Resolves the arguments to the IDs of existing entries.
+///
+/// The ID and `--range` pick entries explicitly, the other options narrow the
+/// selection down. Without an ID or range, the options select from the whole journal.
+fn select_entries(journal: &Journal, args: &RemoveArgs, config: &Config) -> JotResult<Vec<usize>> {
+    let filter = build_filter(args, config)?;
+
+    if let Some(id) = args.id {
+        if journal.get_entry(id).is_none() {
+            return Err(JotError::RemoveError(format!(
+                "Entry with ID {} not found",
+                id
+            )));
+        }
+    }
+    let range = args.range.as_deref().map(parse_range).transpose()?;
+
+    if args.id.is_none() && range.is_none() && filter.is_none() {
+        return Err(JotError::RemoveError(
+            "Specify an ID, --range, --from, --to, --tags, --match or --where".to_string(),
+        ));
+    }
+
+    let explicit = |e: &Entry| {
+        Some(e.id) == args.id || range.is_some_and(|(start, end)| (start..=end).contains(&e.id))
+    };
+    let has_explicit = args.id.is_some() || range.is_some();
+
     Ok(journal
         .get_entries()
         .iter()
-        .filter(|e| e.date <= to_date)
+        .filter(|e| !has_explicit || explicit(e))
+        .filter(|e| filter.as_ref().is_none_or(|f| f.matches(e)))
         .map(|e| e.id)
         .collect())
 }
 
-pub fn execute(journal: &mut Journal, args: RemoveArgs) -> JotResult<()> {
-    let mut to_remove = Vec::new();
+fn confirm_removal(count: usize) -> JotResult<bool> {
+    Confirm::new()
+        .with_prompt(format!("Remove these {} entries?", count))
+        .default(false)
+        .interact()
+        .map_err(|e| {
+            JotError::RemoveError(format!(
+                "Failed to get confirmation ({}); pass --yes to skip it",
+                e
+            ))
+        })
+}
 
-    if let Some(id) = args.id {
-        to_remove.push(id);
+pub fn execute(journal: &mut Journal, args: RemoveArgs, config: &Config) -> JotResult<()> {
+    let to_remove = select_entries(journal, &args, config)?;
+
+    if to_remove.is_empty() {
+        return Err(JotError::RemoveError("No entries to remove".to_string()));
     }
 
-    if let Some(range) = args.range {
-        to_remove.extend(remove_entries_by_range(journal, &range)?);
+    let needs_confirmation = to_remove.len() > 1 && !args.yes;
+    if args.dry_run || needs_confirmation {
+        to_remove
+            .iter()
+            .filter_map(|id| journal.get_entry(*id))
+            .for_each(|e| println!("{}", utils::format_entry(e, config)));
     }
 
-    if let (Some(from), Some(to)) = (args.from.as_ref(), args.to.as_ref()) {
-        to_remove.extend(remove_entries_by_date_range(journal, from, to)?);
-    } else if let Some(from) = args.from {
-        to_remove.extend(remove_entries_from_date(journal, &from)?);
-    } else if let Some(to) = args.to {
-        to_remove.extend(remove_entries_to_date(journal, &to)?);
+    if args.dry_run {
+        println!(
+            "{}",
+            format!(
+                "{} would be removed (dry run, nothing removed)",
+                utils::count_entries(to_remove.len())
+            )
+            .yellow()
+        );
+        return Ok(());
     }
 
-    if to_remove.is_empty() {
-        return Err(JotError::RemoveError("No entries to remove".to_string()));
+    if needs_confirmation && !confirm_removal(to_remove.len())? {
+        println!("Operation cancelled.");
+        return Ok(());
     }
 
-    // Dedup in case of overlapping ranges
-    to_remove.sort_unstable();
-    to_remove.dedup();
+    let removed = journal.remove_entries(&to_remove);
+    storage::save_journal(journal).map_err(|e| JotError::RemoveError(e.to_string()))?;
 
-    to_remove
-        .iter()
-        .try_for_each(|id| remove_entry(journal, *id))?;
+    for entry in &removed {
+        println!("Entry {} removed", entry.id);
+    }
 
     Ok(())
 }
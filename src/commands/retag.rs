@@ -0,0 +1,45 @@
+use crate::{
+    commands::edit::{self, EditArgs},
+    error::{JotError, JotResult},
+    storage::{config::Config, Journal},
+};
+
+#[derive(clap::Args, Clone)]
+pub struct RetagArgs {
+    /// Filter query selecting the entries, e.g. "tag:work from:2024-01-01 deploy"
+    pub query: String,
+    /// Tag to add to every matching entry (can be repeated)
+    #[clap(short, long, value_name = "TAG")]
+    pub add: Vec<String>,
+    /// Tag to remove from every matching entry (can be repeated)
+    #[clap(short, long, value_name = "TAG")]
+    pub remove: Vec<String>,
+    /// Tag to rename on every matching entry (can be repeated)
+    #[clap(long, value_name = "OLD=NEW")]
+    pub rename: Vec<String>,
+    /// Show the affected entries without saving any changes
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+pub fn execute(journal: &mut Journal, args: RetagArgs, config: &Config) -> JotResult<()> {
+    // Without any changes, edit would open a single match in the editor
+    if args.add.is_empty() && args.remove.is_empty() && args.rename.is_empty() {
+        return Err(JotError::CommandError(
+            "Nothing to do; pass --add, --remove or --rename".to_string(),
+        ));
+    }
+
+    edit::execute(
+        journal,
+        EditArgs {
+            where_query: Some(args.query),
+            add_tags: args.add,
+            remove_tags: args.remove,
+            rename_tags: args.rename,
+            dry_run: args.dry_run,
+            ..EditArgs::default()
+        },
+        config,
+    )
+}
@@ -1,12 +1,29 @@
+use chrono::NaiveDate;
+use clap::{Parser, ValueEnum};
+use colored::Colorize;
+use regex::Regex;
+use std::{collections::HashMap, ops::Range};
+
 use crate::{
-    error::JotResult,
-    storage::{config::Config, Journal, Tag},
-    utils::{self, TagMatch},
+    error::{JotError, JotResult},
+    storage::{
+        self,
+        config::{Config, SavedView},
+        index::SearchIndex,
+        Entry, Journal,
+    },
+    utils::{
+        self, fields,
+        filter::{self, EntryFilter, RegexFilter, RegexScope, SortKey},
+        fuzzy, highlight,
+        query::Query,
+        rank, tags, TagMatch,
+    },
 };
 
-// TODO: add regex search
 #[derive(clap::Args, Clone)]
 pub struct SearchArgs {
+    /// Query to search for, e.g. 'deploy OR "release notes" NOT tag:draft'
     pub query: String,
     #[clap(long, value_delimiter = ' ')]
     pub tags: Vec<String>,
@@ -14,109 +31,554 @@ pub struct SearchArgs {
     pub from: Option<String>,
     #[clap(long)]
     pub to: Option<String>,
-    #[clap(short, long)]
+    /// Match text terms fuzzily, so `dplymnt` finds "deployment"
+    #[clap(short, long, conflicts_with = "regex")]
     pub fuzzy: bool,
+    /// Treat the query as a regular expression instead of a filter query
+    #[clap(short, long)]
+    pub regex: bool,
+    /// Parts of the entry the regular expression is matched against
+    #[clap(
+        long = "in",
+        value_enum,
+        value_name = "PARTS",
+        value_delimiter = ',',
+        default_value = "body",
+        requires = "regex"
+    )]
+    pub scopes: Vec<RegexScope>,
     #[clap(short, long)]
     pub all: bool,
     #[clap(short, long)]
     pub case_sensitive: bool,
+    /// Only search entries of these kinds, e.g. task,log
+    #[clap(short, long = "kind", value_name = "KIND", value_delimiter = ',')]
+    pub kinds: Vec<String>,
+    /// Only search entries matching a filter query, e.g. "hours>2 project=apollo"
+    #[clap(short, long = "where", value_name = "QUERY")]
+    pub where_query: Option<String>,
+    /// Only search entries modified on or after this date
+    #[clap(long, value_name = "DATE")]
+    pub modified_since: Option<String>,
+    /// Order in which results are listed; the most relevant come first by default
+    #[clap(long, value_enum)]
+    pub sort: Option<SortKey>,
+    /// Show at most this many results
+    #[clap(short, long)]
+    pub limit: Option<usize>,
+    /// Only show this many lines around each match, like `grep -C`
+    #[clap(short = 'C', long, value_name = "LINES")]
+    pub context: Option<usize>,
+    /// Search every journal: this one and those listed under `[journals]` in
+    /// the config
+    #[clap(short, long)]
+    pub everywhere: bool,
+    /// Save this search under a name, to run again with `xlog view @NAME`
+    #[clap(long, value_name = "NAME")]
+    pub save: Option<String>,
+}
+
+/// Parses the arguments of a saved search
+#[derive(clap::Parser)]
+#[command(no_binary_name = true)]
+struct SavedSearch {
+    #[clap(flatten)]
+    args: SearchArgs,
 }
 
-fn check_date_range(
-    entry_date: chrono::NaiveDate,
-    from: &Option<String>,
-    to: &Option<String>,
-) -> bool {
-    if let Some(from_date) = from {
-        let date = utils::parse_date(from_date);
-        if date > entry_date {
-            return false;
+impl SearchArgs {
+    /// The command-line arguments that give this search, without `--save`
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut push = |flag: &str, value: Option<String>| {
+            args.push(flag.to_string());
+            args.extend(value);
+        };
+
+        if !self.tags.is_empty() {
+            push("--tags", Some(self.tags.join(" ")));
+        }
+        if let Some(from) = &self.from {
+            push("--from", Some(from.clone()));
+        }
+        if let Some(to) = &self.to {
+            push("--to", Some(to.clone()));
+        }
+        if self.fuzzy {
+            push("--fuzzy", None);
+        }
+        if self.regex {
+            push("--regex", None);
+            let scopes = self.scopes.iter().map(value_name).collect::<Vec<_>>();
+            push("--in", Some(scopes.join(",")));
+        }
+        if self.all {
+            push("--all", None);
+        }
+        if self.case_sensitive {
+            push("--case-sensitive", None);
+        }
+        if !self.kinds.is_empty() {
+            push("--kind", Some(self.kinds.join(",")));
+        }
+        if let Some(query) = &self.where_query {
+            push("--where", Some(query.clone()));
+        }
+        if let Some(date) = &self.modified_since {
+            push("--modified-since", Some(date.clone()));
+        }
+        if let Some(sort) = self.sort {
+            push("--sort", Some(value_name(&sort)));
+        }
+        if let Some(limit) = self.limit {
+            push("--limit", Some(limit.to_string()));
+        }
+        if let Some(context) = self.context {
+            push("--context", Some(context.to_string()));
+        }
+        if self.everywhere {
+            push("--everywhere", None);
         }
-    }
 
-    if let Some(to_date) = to {
-        let date = utils::parse_date(to_date);
-        if date < entry_date {
-            return false;
+        // A query starting with `-` would be taken for a flag
+        if self.query.starts_with('-') {
+            args.push("--".to_string());
         }
+        args.push(self.query.clone());
+        args
     }
 
-    true
+    /// The search saved in the config as `name`
+    pub fn saved(name: &str, config: &Config) -> JotResult<SearchArgs> {
+        let view = config.views.get(name).ok_or_else(|| {
+            JotError::SearchError(format!(
+                "No saved search named '{}'. Run 'xlog views' to list them",
+                name
+            ))
+        })?;
+
+        SavedSearch::try_parse_from(&view.search)
+            .map(|saved| saved.args)
+            .map_err(|e| {
+                JotError::SearchError(format!("Saved search '{}' is invalid: {}", name, e))
+            })
+    }
 }
 
-fn check_content_match(content: &str, term: &str, fuzzy: bool) -> bool {
-    if fuzzy {
-        utils::fuzzy_match(content, term)
-    } else {
-        content.contains(term)
+/// The name of an option value as it is typed on the command line
+fn value_name(value: &impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Stores the search in the config under `name`, replacing any search saved
+/// under that name before
+fn save_search(name: &str, args: &SearchArgs) -> JotResult<()> {
+    let name = name.trim().trim_start_matches('@');
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(JotError::SearchError(format!(
+            "Invalid name '{}' for a saved search: it must be a single word",
+            name
+        )));
     }
+
+    let mut config = storage::load_config()?;
+    config.views.insert(
+        name.to_string(),
+        SavedView {
+            search: args.to_args(),
+        },
+    );
+    storage::save_config(&config)?;
+
+    println!(
+        "{}",
+        format!(
+            "Saved search as @{}. Run it with 'xlog view @{}'",
+            name, name
+        )
+        .green()
+    );
+    Ok(())
 }
 
-fn print_results(found: Vec<String>, term: &str) {
-    println!("{} entries found", found.len());
-    found.iter().for_each(|e| {
-        if term.is_empty() {
-            println!("{}", e);
-        } else {
-            let highlighted =
-                e.to_lowercase()
-                    .match_indices(&term)
-                    .fold(e.to_string(), |acc, (i, _)| {
-                        let orig_match = &acc[i..i + term.len()];
-                        acc.replacen(orig_match, &format!("\x1b[42m{}\x1b[0m", orig_match), 1)
-                    });
-            println!("{}", highlighted);
+/// Label of the default journal when searching several
+const HOME_JOURNAL: &str = "home";
+
+fn parse_search_date(date: &str) -> JotResult<NaiveDate> {
+    utils::parse_date(date)
+        .map_err(|e| JotError::SearchError(format!("Invalid date '{}': {}", date, e)))
+}
+
+/// Byte ranges of every capture group of each match, or of the whole match if
+/// the expression has no groups. Nested groups merge into the outermost one
+/// when highlighted.
+fn capture_ranges(text: &str, regex: &Regex) -> Vec<Range<usize>> {
+    regex
+        .captures_iter(text)
+        .flat_map(|captures| {
+            let groups = captures.iter().skip(usize::from(captures.len() > 1));
+            groups.flatten().map(|m| m.range()).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// How matches are found for highlighting in the results
+enum Highlight<'a> {
+    /// Occurrences of the terms and, ignoring case, words sharing their stems
+    Terms(&'a [String], bool),
+    Fuzzy(&'a [String], bool),
+    Regex(&'a Regex),
+}
+
+impl Highlight<'_> {
+    fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        match *self {
+            Highlight::Terms(terms, case_sensitive) => terms
+                .iter()
+                .flat_map(|term| {
+                    let mut ranges = highlight::find_term(text, term, case_sensitive);
+                    if !case_sensitive {
+                        ranges.extend(highlight::find_stemmed(text, term));
+                    }
+                    ranges
+                })
+                .collect(),
+            Highlight::Fuzzy(terms, case_sensitive) => terms
+                .iter()
+                .filter_map(|term| fuzzy::fuzzy_match(text, term, case_sensitive))
+                .flat_map(|found| found.ranges(text))
+                .collect(),
+            Highlight::Regex(regex) => capture_ranges(text, regex),
+        }
+    }
+}
+
+fn print_results(
+    hits: Vec<Hit>,
+    highlight: Highlight<'_>,
+    context: Option<usize>,
+    config: &Config,
+) {
+    let color = colored::control::SHOULD_COLORIZE.should_colorize();
+    println!("{} entries found", hits.len());
+    for Hit {
+        source,
+        entry,
+        score,
+    } in hits
+    {
+        if let Some(score) = score {
+            println!("{}", format!("score {:.2}", score).dimmed());
+        }
+        let header = utils::format_header(entry, config);
+        match source {
+            Some(source) => println!("{} {}", format!("{}:", source).magenta().bold(), header),
+            None => println!("{}", header),
+        }
+        if !entry.fields.is_empty() {
+            println!("{}", fields::format_fields(&entry.fields).cyan());
+        }
+
+        let text = match &entry.title {
+            Some(title) => format!("{}\n{}", title, entry.body),
+            None => entry.body.clone(),
+        };
+        let line_count = text.split('\n').count();
+        let mut next = 0;
+        let ranges = highlight.ranges(&text);
+        for (n, line) in highlight::excerpt(&text, &ranges, context, color) {
+            if n > next {
+                println!("{}", "...".dimmed());
+            }
+            if n == 0 && entry.title.is_some() {
+                println!("{}", line.bold());
+            } else {
+                println!("{}", line);
+            }
+            next = n + 1;
         }
+        if next < line_count {
+            println!("{}", "...".dimmed());
+        }
+        println!("{}", "-".repeat(40));
+    }
+}
+
+/// Scores entries by how well each text term fuzzily matches their title and
+/// body, so tight matches on word starts come first
+fn fuzzy_scores(entries: &[&Entry], terms: &[String], case_sensitive: bool) -> HashMap<usize, f64> {
+    entries
+        .iter()
+        .map(|e| {
+            let text = match &e.title {
+                Some(title) => format!("{}\n{}", title, e.body),
+                None => e.body.clone(),
+            };
+            let score = terms
+                .iter()
+                .filter_map(|term| fuzzy::fuzzy_match(&text, term, case_sensitive))
+                .map(|found| found.score)
+                .sum::<i32>();
+            (e.id, f64::from(score))
+        })
+        .collect()
+}
+
+/// A matching entry with its relevance score, and the journal it came from when
+/// searching several
+struct Hit<'a> {
+    source: Option<&'a str>,
+    entry: &'a Entry,
+    score: Option<f64>,
+}
+
+/// Finds the entries of one journal matching the search, scored by relevance
+/// against that journal's index when there are words to rank by
+fn search_journal<'a>(
+    journal: &'a Journal,
+    index: &SearchIndex,
+    filter: &mut EntryFilter,
+    where_filter: Option<&EntryFilter>,
+    terms: &[String],
+    args: &SearchArgs,
+) -> Vec<(&'a Entry, Option<f64>)> {
+    filter.use_index(index);
+
+    let matching: Vec<_> = journal
+        .get_entries()
+        .iter()
+        .filter(|e| filter.matches(e) && where_filter.is_none_or(|f| f.matches(e)))
+        .collect();
+
+    // Regex matches have no words to rank by
+    let scores = (!args.regex && !terms.is_empty()).then(|| {
+        if args.fuzzy {
+            return fuzzy_scores(&matching, terms, args.case_sensitive);
+        }
+        let terms = filter
+            .query
+            .iter()
+            .flat_map(Query::text_terms)
+            .collect::<Vec<_>>();
+        rank::bm25_scores(index, &terms)
     });
+
+    matching
+        .into_iter()
+        .map(|e| {
+            let score = scores
+                .as_ref()
+                .map(|s| s.get(&e.id).copied().unwrap_or(0.0));
+            (e, score)
+        })
+        .collect()
 }
 
 pub fn execute(journal: &Journal, args: SearchArgs, config: &Config) -> JotResult<()> {
-    let term = if args.case_sensitive {
-        args.query
-    } else {
-        args.query.to_lowercase()
+    let mut filter = EntryFilter {
+        tags: args
+            .tags
+            .iter()
+            .map(|t| {
+                tags::normalize_tag(t, &config.journal_cfg)
+                    .ok_or_else(|| JotError::SearchError(format!("Invalid tag '{}'", t)))
+            })
+            .collect::<JotResult<_>>()?,
+        match_type: if args.all {
+            TagMatch::All
+        } else {
+            TagMatch::Any
+        },
+        from: args.from.as_deref().map(parse_search_date).transpose()?,
+        to: args.to.as_deref().map(parse_search_date).transpose()?,
+        kinds: args.kinds.iter().map(|k| k.trim().to_lowercase()).collect(),
+        modified_since: args
+            .modified_since
+            .as_deref()
+            .map(parse_search_date)
+            .transpose()?,
+        query: if args.regex {
+            None
+        } else {
+            Query::parse(&args.query, &config.journal_cfg)?
+        },
+        regex: args
+            .regex
+            .then(|| RegexFilter::new(&args.query, &args.scopes, args.case_sensitive))
+            .transpose()?,
+        case_sensitive: args.case_sensitive,
+        fuzzy: args.fuzzy,
+        tag_cfg: Some(config.journal_cfg.clone()),
+        ..EntryFilter::default()
     };
 
-    let entries = journal.get_entries();
+    let where_filter = args
+        .where_query
+        .as_deref()
+        .map(|q| EntryFilter::parse(q, &config.journal_cfg))
+        .transpose()?;
 
-    if entries.is_empty() {
+    let terms = filter
+        .query
+        .iter()
+        .flat_map(Query::text_terms)
+        .map(|t| t.trim_end_matches('*').to_string())
+        .collect::<Vec<_>>();
+
+    let named = if args.everywhere {
+        storage::load_named_journals(config)?
+    } else {
+        Vec::new()
+    };
+    let mut journals = vec![(args.everywhere.then_some(HOME_JOURNAL), journal)];
+    journals.extend(named.iter().map(|(name, j)| (Some(name.as_str()), j)));
+
+    if journals.iter().all(|(_, j)| j.get_entries().is_empty()) {
         println!("No entries found.");
     } else {
-        let found: Vec<String> = entries
-            .iter()
-            .filter(|e| {
-                let content = if args.case_sensitive {
-                    e.body.clone()
-                } else {
-                    e.body.to_lowercase()
-                };
-
-                let content_matches = check_content_match(&content, &term, args.fuzzy);
-                let dates_match = check_date_range(e.date, &args.from, &args.to);
-
-                let match_type = if args.all {
-                    TagMatch::All
-                } else {
-                    TagMatch::Any
-                };
-
-                let tags_match = utils::do_tags_match(
-                    &args
-                        .tags
-                        .iter()
-                        .map(|t| Tag::new(t.to_string()))
-                        .collect::<Vec<_>>(),
-                    &e.tags,
-                    match_type,
-                );
-
-                content_matches && dates_match && tags_match
-            })
-            .map(|e| utils::format_entry(e, config.journal_cfg.clone()))
-            .collect();
+        let mut hits = Vec::new();
+        for (i, (source, journal)) in journals.into_iter().enumerate() {
+            // Only the active journal keeps an index file; the others are
+            // indexed in memory so searching never writes beside them
+            let index = if i == 0 {
+                storage::index::update_index(journal)?
+            } else {
+                SearchIndex::build(journal.get_entries())
+            };
+            let mut found = search_journal(
+                journal,
+                &index,
+                &mut filter,
+                where_filter.as_ref(),
+                &terms,
+                &args,
+            );
+            // Scores come from each journal's own index, so they are only
+            // ranked against each other within a journal
+            if args.sort.is_none() {
+                found.sort_by(|a, b| b.1.unwrap_or(0.0).total_cmp(&a.1.unwrap_or(0.0)));
+            }
+            hits.extend(found.into_iter().map(|(entry, score)| Hit {
+                source,
+                entry,
+                score,
+            }));
+        }
+
+        if let Some(key) = args.sort {
+            filter::sort_by_entry(&mut hits, key, |hit| hit.entry);
+        }
+        hits.truncate(args.limit.unwrap_or(usize::MAX));
 
-        print_results(found, &term);
+        let highlight = match &filter.regex {
+            Some(regex) => Highlight::Regex(&regex.regex),
+            None if args.fuzzy => Highlight::Fuzzy(&terms, args.case_sensitive),
+            None => Highlight::Terms(&terms, args.case_sensitive),
+        };
+        print_results(hits, highlight, args.context, config);
+    }
+
+    if let Some(name) = &args.save {
+        save_search(name, &args)?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_ranges() {
+        let regex = Regex::new(r"v(\d+)\.(\d+)").unwrap();
+        assert_eq!(
+            capture_ranges("v2.1 and v10.4", &regex),
+            vec![1..2, 3..4, 10..12, 13..14]
+        );
+
+        let regex = Regex::new(r"deploy\w*").unwrap();
+        assert_eq!(capture_ranges("deployed, not shipped", &regex), vec![0..8]);
+    }
+
+    #[test]
+    fn test_highlight_ranges() {
+        let terms = vec!["deploying".to_string()];
+        let text = "Déploy: we DEPLOYED it";
+        assert_eq!(Highlight::Terms(&terms, false).ranges(text), vec![12..20]);
+
+        let terms = vec!["dply".to_string()];
+        assert_eq!(
+            Highlight::Fuzzy(&terms, false).ranges("Deployed it"),
+            vec![0..1, 2..4, 5..6]
+        );
+    }
+
+    fn parse_args(args: &[&str]) -> SearchArgs {
+        SavedSearch::try_parse_from(args).unwrap().args
+    }
+
+    #[test]
+    fn test_to_args() {
+        let args = parse_args(&[
+            "deploy OR ship",
+            "--tags",
+            "work ops",
+            "-k",
+            "task,log",
+            "--where",
+            "hours>2",
+            "--sort",
+            "modified",
+            "-l",
+            "5",
+            "--save",
+            "standup",
+        ]);
+        let saved = args.to_args();
+        assert!(!saved.contains(&"--save".to_string()));
+        assert_eq!(
+            parse_args(&saved.iter().map(String::as_str).collect::<Vec<_>>()).to_args(),
+            saved
+        );
+        assert_eq!(saved.last().unwrap(), "deploy OR ship");
+
+        let args = parse_args(&["--regex", "--in", "title,tags", "--", "-v\\d"]);
+        let saved = args.to_args();
+        assert_eq!(saved, vec!["--regex", "--in", "title,tags", "--", "-v\\d"]);
+    }
+
+    #[test]
+    fn test_saved() {
+        let mut config = Config::default();
+        config.views.insert(
+            "standup".to_string(),
+            SavedView {
+                search: vec!["deploy".to_string(), "--limit".to_string(), "3".to_string()],
+            },
+        );
+        config.views.insert(
+            "broken".to_string(),
+            SavedView {
+                search: vec!["--no-such-flag".to_string()],
+            },
+        );
+
+        let args = SearchArgs::saved("standup", &config).unwrap();
+        assert_eq!(args.query, "deploy");
+        assert_eq!(args.limit, Some(3));
+        assert!(SearchArgs::saved("broken", &config).is_err());
+        assert!(SearchArgs::saved("missing", &config).is_err());
+    }
+
+    #[test]
+    fn test_fuzzy_scores() {
+        let close = Entry::new(0, "deployment notes".to_string(), Vec::new());
+        let scattered = Entry::new(1, "dumpling daily".to_string(), Vec::new());
+        let scores = fuzzy_scores(&[&close, &scattered], &["dply".to_string()], false);
+        assert!(scores[&0] > scores[&1]);
+    }
+}
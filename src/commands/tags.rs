@@ -0,0 +1,348 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::NaiveDate;
+use colored::Colorize;
+
+use crate::{
+    error::{JotError, JotResult},
+    storage::{
+        self,
+        config::{Config, JournalConfig},
+        Entry, Journal, Tag,
+    },
+    utils,
+};
+
+#[derive(clap::Args, Clone)]
+pub struct TagsArgs {
+    #[command(subcommand)]
+    pub action: Option<TagsAction>,
+    /// Sort the tag list by name instead of usage count
+    #[clap(short, long)]
+    pub name: bool,
+    /// Show nested tags as a tree with entry counts
+    #[clap(long)]
+    pub tree: bool,
+}
+
+#[derive(clap::Subcommand, Clone)]
+pub enum TagsAction {
+    /// Rename a tag on every entry, moving any nested tags along with it
+    Rename { old: String, new: String },
+    /// Merge tags into one, e.g. `xlog tags merge a b into c`
+    Merge {
+        #[clap(num_args = 3.., required = true, value_name = "TAGS")]
+        args: Vec<String>,
+    },
+    /// Delete a tag and the tags nested below it from every entry
+    #[command(visible_alias = "rm")]
+    Delete { tag: String },
+    /// Rewrite stored tags with the current lowercase_tags and
+    /// tag_normalization settings, e.g. after turning them on
+    Normalize,
+}
+
+/// Usage statistics for a single tag
+struct TagStats {
+    tag: Tag,
+    count: usize,
+    last_used: NaiveDate,
+}
+
+/// A level in the tag hierarchy; `count` is the number of entries tagged with
+/// this tag or anything nested below it
+#[derive(Default)]
+struct TagNode {
+    count: usize,
+    children: BTreeMap<String, TagNode>,
+}
+
+pub fn execute(journal: &mut Journal, args: TagsArgs, config: &Config) -> JotResult<()> {
+    let cfg = &config.journal_cfg;
+    match args.action {
+        None if args.tree => {
+            print_tree(journal);
+            Ok(())
+        }
+        None => {
+            list_tags(journal, args.name);
+            Ok(())
+        }
+        Some(TagsAction::Rename { old, new }) => {
+            let old = parse_tag_arg(&old, cfg)?;
+            let new = parse_tag_arg(&new, cfg)?;
+            let changed = rewrite_tags(journal, std::slice::from_ref(&old), Some(&new), cfg)?;
+            println!(
+                "Renamed {} to {} on {} entries",
+                format!("#{}", old).bright_yellow(),
+                format!("#{}", new).bright_yellow(),
+                changed
+            );
+            Ok(())
+        }
+        Some(TagsAction::Merge { args }) => {
+            let (sources, target) = parse_merge_args(&args, cfg)?;
+            let changed = rewrite_tags(journal, &sources, Some(&target), cfg)?;
+            println!(
+                "Merged {} into {} on {} entries",
+                sources
+                    .iter()
+                    .map(|t| format!("#{}", t))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .bright_yellow(),
+                format!("#{}", target).bright_yellow(),
+                changed
+            );
+            Ok(())
+        }
+        Some(TagsAction::Delete { tag }) => {
+            let tag = parse_tag_arg(&tag, cfg)?;
+            let changed = rewrite_tags(journal, std::slice::from_ref(&tag), None, cfg)?;
+            println!(
+                "Deleted {} from {} entries",
+                format!("#{}", tag).bright_yellow(),
+                changed
+            );
+            Ok(())
+        }
+        Some(TagsAction::Normalize) => {
+            let changed = journal
+                .get_entries()
+                .iter()
+                .filter_map(|entry| {
+                    let mut entry = entry.clone();
+                    utils::tags::renormalize_entry(&mut entry, cfg).then_some(entry)
+                })
+                .collect::<Vec<_>>();
+
+            let count = changed.len();
+            for entry in changed {
+                journal.update_entry(entry);
+            }
+            if count > 0 {
+                storage::save_journal(journal)?;
+            }
+            println!("Normalized tags on {} entries", count);
+            Ok(())
+        }
+    }
+}
+
+fn collect_stats(entries: &[Entry]) -> Vec<TagStats> {
+    let mut stats: HashMap<&Tag, TagStats> = HashMap::new();
+    for entry in entries {
+        for tag in &entry.tags {
+            stats
+                .entry(tag)
+                .and_modify(|s| {
+                    s.count += 1;
+                    s.last_used = s.last_used.max(entry.date);
+                })
+                .or_insert_with(|| TagStats {
+                    tag: tag.clone(),
+                    count: 1,
+                    last_used: entry.date,
+                });
+        }
+    }
+
+    stats.into_values().collect()
+}
+
+fn list_tags(journal: &Journal, by_name: bool) {
+    let mut stats = collect_stats(journal.get_entries());
+    if stats.is_empty() {
+        println!("No tags found.");
+        return;
+    }
+
+    if by_name {
+        stats.sort_by(|a, b| a.tag.name.cmp(&b.tag.name));
+    } else {
+        stats.sort_by(|a, b| b.count.cmp(&a.count).then(a.tag.name.cmp(&b.tag.name)));
+    }
+
+    let width = stats.iter().map(|s| s.tag.name.len()).max().unwrap_or(0);
+    println!("{} tags found", stats.len());
+    for s in stats {
+        println!(
+            "{:<width$}  {:>5}  last used {}",
+            s.tag.name.bright_yellow(),
+            s.count,
+            s.last_used.format("%Y-%m-%d").to_string().bright_blue(),
+            width = width
+        );
+    }
+}
+
+fn build_tree(entries: &[Entry]) -> TagNode {
+    let mut root = TagNode::default();
+    for entry in entries {
+        // Each node is counted once per entry, even if several of its tags share it
+        let mut paths = BTreeSet::new();
+        for tag in &entry.tags {
+            let segments = tag.segments().collect::<Vec<_>>();
+            for depth in 1..=segments.len() {
+                paths.insert(segments[..depth].to_vec());
+            }
+        }
+
+        for path in paths {
+            let mut node = &mut root;
+            for segment in path {
+                node = node.children.entry(segment.to_string()).or_default();
+            }
+            node.count += 1;
+        }
+    }
+
+    root
+}
+
+fn print_tree(journal: &Journal) {
+    let root = build_tree(journal.get_entries());
+    if root.children.is_empty() {
+        println!("No tags found.");
+        return;
+    }
+
+    fn print_node(name: &str, node: &TagNode, depth: usize) {
+        println!(
+            "{}{} ({})",
+            "  ".repeat(depth),
+            name.bright_yellow(),
+            node.count
+        );
+        for (child_name, child) in &node.children {
+            print_node(child_name, child, depth + 1);
+        }
+    }
+
+    for (name, node) in &root.children {
+        print_node(name, node, 0);
+    }
+}
+
+/// Parses `a b into c` into the source tags and the target tag
+fn parse_merge_args(args: &[String], cfg: &JournalConfig) -> JotResult<(Vec<Tag>, Tag)> {
+    match args {
+        [sources @ .., into, target] if into == "into" && !sources.is_empty() => Ok((
+            sources
+                .iter()
+                .map(|t| parse_tag_arg(t, cfg))
+                .collect::<JotResult<_>>()?,
+            parse_tag_arg(target, cfg)?,
+        )),
+        _ => Err(JotError::CommandError(
+            "Invalid merge, expected: xlog tags merge <TAG>... into <TAG>".to_string(),
+        )),
+    }
+}
+
+fn parse_tag_arg(tag: &str, cfg: &JournalConfig) -> JotResult<Tag> {
+    utils::tags::normalize_tag(tag, cfg)
+        .ok_or_else(|| JotError::CommandError(format!("Invalid tag '{}'", tag)))
+}
+
+/// Renames every tag in `old` to `new` (or deletes them) across the journal
+/// and saves it once. Returns the number of entries that changed.
+fn rewrite_tags(
+    journal: &mut Journal,
+    old: &[Tag],
+    new: Option<&Tag>,
+    cfg: &JournalConfig,
+) -> JotResult<usize> {
+    if let Some(missing) = old.iter().find(|tag| {
+        !journal
+            .get_entries()
+            .iter()
+            .any(|e| utils::has_tag(&e.tags, tag, Some(cfg)))
+    }) {
+        return Err(JotError::CommandError(format!(
+            "Tag '{}' not found",
+            missing
+        )));
+    }
+
+    let changed = journal
+        .get_entries()
+        .iter()
+        .filter_map(|entry| {
+            let mut entry = entry.clone();
+            let mut changed = false;
+            for tag in old {
+                changed |= utils::tags::rename_tag(&mut entry, tag, new, cfg);
+            }
+            changed.then_some(entry)
+        })
+        .collect::<Vec<_>>();
+
+    let count = changed.len();
+    for entry in changed {
+        journal.update_entry(entry);
+    }
+    storage::save_journal(journal)?;
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_merge_args() {
+        let args = ["a", "#b", "into", "c"].map(String::from);
+        let cfg = JournalConfig::default();
+        let (sources, target) = parse_merge_args(&args, &cfg).unwrap();
+        assert_eq!(
+            sources,
+            vec![Tag::new("a".to_string()), Tag::new("b".to_string())]
+        );
+        assert_eq!(target, Tag::new("c".to_string()));
+
+        assert!(parse_merge_args(&["a", "b", "c"].map(String::from), &cfg).is_err());
+        assert!(parse_merge_args(&["into", "c"].map(String::from), &cfg).is_err());
+        assert!(parse_merge_args(&["a", "into", "#"].map(String::from), &cfg).is_err());
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let entries = [
+            Entry::new(
+                0,
+                String::new(),
+                vec![
+                    Tag::new("work/clientA/billing".to_string()),
+                    Tag::new("work/clientA".to_string()),
+                ],
+            ),
+            Entry::new(1, String::new(), vec![Tag::new("work/clientB".to_string())]),
+        ];
+
+        let root = build_tree(&entries);
+        let work = &root.children["work"];
+        assert_eq!(work.count, 2);
+        assert_eq!(work.children["clientA"].count, 1);
+        assert_eq!(work.children["clientA"].children["billing"].count, 1);
+        assert_eq!(work.children["clientB"].count, 1);
+    }
+
+    #[test]
+    fn test_collect_stats() {
+        let mut old = Entry::new(0, String::new(), vec![Tag::new("a".to_string())]);
+        old.date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let new = Entry::new(
+            1,
+            String::new(),
+            vec![Tag::new("a".to_string()), Tag::new("b".to_string())],
+        );
+
+        let stats = collect_stats(&[old, new.clone()]);
+        let a = stats.iter().find(|s| s.tag.name == "a").unwrap();
+        assert_eq!(a.count, 2);
+        assert_eq!(a.last_used, new.date);
+        assert_eq!(stats.len(), 2);
+    }
+}
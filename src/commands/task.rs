@@ -0,0 +1,153 @@
+use colored::Colorize;
+use std::cmp::Reverse;
+
+use crate::{
+    error::{JotError, JotResult},
+    storage::{self, config::Config, Entry, Journal, TaskStatus},
+    utils::{self, filter::EntryFilter},
+};
+
+#[derive(clap::Args, Clone)]
+pub struct TodoArgs {
+    /// Also list done and cancelled tasks
+    #[clap(short, long)]
+    pub all: bool,
+}
+
+#[derive(clap::Args, Clone)]
+pub struct TaskIdsArgs {
+    /// IDs of the tasks
+    #[clap(required = true)]
+    pub ids: Vec<usize>,
+}
+
+/// Lists open tasks, highest priority first and in-progress ones before the rest
+pub fn todo(journal: &Journal, args: TodoArgs, config: &Config) -> JotResult<()> {
+    let filter = EntryFilter {
+        statuses: if args.all {
+            Vec::new()
+        } else {
+            vec![TaskStatus::Open, TaskStatus::InProgress]
+        },
+        ..EntryFilter::default()
+    };
+
+    let mut tasks: Vec<_> = journal
+        .get_entries()
+        .iter()
+        .filter(|e| e.task.is_some() && filter.matches(e))
+        .collect();
+    sort_tasks(&mut tasks);
+
+    if tasks.is_empty() {
+        println!("{}", "Nothing to do.".green());
+        return Ok(());
+    }
+
+    println!("{} tasks", tasks.len());
+    for task in tasks {
+        println!("{}", utils::format_entry(task, config));
+    }
+
+    Ok(())
+}
+
+/// Orders open tasks before finished ones, then by priority, highest first,
+/// with in-progress tasks ahead of others of the same priority
+fn sort_tasks(tasks: &mut [&Entry]) {
+    tasks.sort_by_key(|e| {
+        let task = e.task.clone().unwrap_or_default();
+        (
+            !task.status.is_open(),
+            Reverse(task.priority),
+            task.status != TaskStatus::InProgress,
+            e.id,
+        )
+    });
+}
+
+/// Marks tasks as done
+pub fn done(journal: &mut Journal, args: TaskIdsArgs) -> JotResult<()> {
+    set_status(journal, &args.ids, TaskStatus::Done)
+}
+
+/// Marks done or cancelled tasks as open again
+pub fn reopen(journal: &mut Journal, args: TaskIdsArgs) -> JotResult<()> {
+    set_status(journal, &args.ids, TaskStatus::Open)
+}
+
+/// Sets the status of every given task and saves the journal once.
+/// Nothing is changed if any of the IDs is not a task.
+fn set_status(journal: &mut Journal, ids: &[usize], status: TaskStatus) -> JotResult<()> {
+    let mut updated = Vec::new();
+    for id in ids {
+        let entry = journal
+            .get_entry(*id)
+            .ok_or_else(|| JotError::CommandError(format!("Entry with ID {} not found", id)))?;
+        let task = entry.task.as_ref().ok_or_else(|| {
+            JotError::CommandError(format!(
+                "Entry {} is a {}, not a task; change it with `xlog edit {} --kind task`",
+                id, entry.kind, id
+            ))
+        })?;
+
+        if task.status == status {
+            println!("Task {} is already {}", id, status);
+            continue;
+        }
+
+        let mut entry = entry.clone();
+        if let Some(task) = &mut entry.task {
+            task.set_status(status);
+        }
+        updated.push(entry);
+    }
+
+    if updated.is_empty() {
+        return Ok(());
+    }
+
+    for entry in updated {
+        println!(
+            "Task {} marked {}: {}",
+            entry.id.to_string().bold(),
+            status.to_string().green(),
+            entry.display_title()
+        );
+        journal.update_entry(entry);
+    }
+    storage::save_journal(journal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{Priority, TASK_KIND};
+
+    fn task(id: usize, status: TaskStatus, priority: Priority) -> Entry {
+        let mut entry = Entry::new(id, format!("task {}", id), vec![]);
+        entry.kind = TASK_KIND.to_string();
+        entry.sync_task();
+        if let Some(task) = &mut entry.task {
+            task.set_status(status);
+            task.priority = priority;
+        }
+        entry
+    }
+
+    #[test]
+    fn test_sort_tasks() {
+        let entries = [
+            task(0, TaskStatus::Done, Priority::High),
+            task(1, TaskStatus::Open, Priority::Low),
+            task(2, TaskStatus::Open, Priority::High),
+            task(3, TaskStatus::InProgress, Priority::High),
+            task(4, TaskStatus::Open, Priority::Medium),
+        ];
+        let mut tasks = entries.iter().collect::<Vec<_>>();
+        sort_tasks(&mut tasks);
+
+        let ids = tasks.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![3, 2, 4, 1, 0]);
+    }
+}
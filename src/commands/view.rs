@@ -1,13 +1,45 @@
+use chrono::NaiveDate;
+use std::str::FromStr;
+
 use crate::{
+    commands::search::{self, SearchArgs},
     error::{JotError, JotResult},
-    storage::{config::Config, Entry, Journal, Tag},
-    utils::{self, TagMatch},
+    storage::{config::Config, Entry, Journal, TaskStatus},
+    utils::{
+        self,
+        filter::{self, EntryFilter, SortKey},
+        tags, TagMatch,
+    },
 };
 
+/// What `xlog view` was asked to show instead of a filtered list
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViewTarget {
+    /// A single entry, by ID
+    Id(usize),
+    /// A saved search, by name
+    Saved(String),
+}
+
+impl FromStr for ViewTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('@') {
+            Some(name) if !name.is_empty() => Ok(ViewTarget::Saved(name.to_string())),
+            _ => s
+                .parse()
+                .map(ViewTarget::Id)
+                .map_err(|_| format!("'{}' is neither an entry ID nor @name of a saved search", s)),
+        }
+    }
+}
+
 #[derive(clap::Args, Clone)]
 pub struct ViewArgs {
-    /// ID of the specific entry to view
-    pub id: Option<usize>,
+    /// ID of the specific entry to view, or @name of a saved search to run
+    #[clap(value_name = "ID|@NAME")]
+    pub target: Option<ViewTarget>,
     /// View entries starting from this date
     #[clap(short, long)]
     pub from: Option<String>,
@@ -23,6 +55,40 @@ pub struct ViewArgs {
     /// Whether all tags should match or any tag should match
     #[clap(short, long)]
     pub all: bool,
+    /// Only show entries of these kinds, e.g. task,log
+    #[clap(short, long = "kind", value_name = "KIND", value_delimiter = ',')]
+    pub kinds: Vec<String>,
+    /// Only show tasks with these statuses, e.g. open,in-progress
+    #[clap(short, long = "status", value_enum, value_delimiter = ',')]
+    pub statuses: Vec<TaskStatus>,
+    /// Only show entries with unchecked checklist items
+    #[clap(short, long)]
+    pub unchecked: bool,
+    /// Only show entries matching a filter query, e.g. "hours>2 project=apollo"
+    #[clap(short, long = "where", value_name = "QUERY")]
+    pub where_query: Option<String>,
+    /// Only show entries modified on or after this date
+    #[clap(long, value_name = "DATE")]
+    pub modified_since: Option<String>,
+    /// Order in which entries are listed, by ID if not given
+    #[clap(long, value_enum)]
+    pub sort: Option<SortKey>,
+}
+
+impl ViewArgs {
+    /// Returns true if any option selecting or ordering entries was given
+    fn has_filters(&self) -> bool {
+        self.from.is_some()
+            || self.to.is_some()
+            || !self.tags.is_empty()
+            || self.all
+            || !self.kinds.is_empty()
+            || !self.statuses.is_empty()
+            || self.unchecked
+            || self.where_query.is_some()
+            || self.modified_since.is_some()
+            || self.sort.is_some()
+    }
 }
 
 fn print_formatted_entries(entries: &[&Entry], config: &Config) {
@@ -32,7 +98,7 @@ fn print_formatted_entries(entries: &[&Entry], config: &Config) {
         println!("{} entries found", entries.len());
         entries
             .iter()
-            .for_each(|e| println!("{}", utils::format_entry(e, config.journal_cfg.clone())));
+            .for_each(|e| println!("{}", utils::format_entry(e, config)));
     }
 }
 
@@ -43,56 +109,120 @@ fn view_recent(journal: &Journal, config: &Config) {
     }
 }
 
-fn filter_entries<'a>(entries: &'a [Entry], args: &ViewArgs) -> Vec<&'a Entry> {
-    entries
+fn filter_entries<'a>(
+    entries: &'a [Entry],
+    args: &ViewArgs,
+    config: &Config,
+) -> JotResult<Vec<&'a Entry>> {
+    let filter = EntryFilter {
+        tags: args
+            .tags
+            .iter()
+            .map(|t| {
+                tags::normalize_tag(t, &config.journal_cfg)
+                    .ok_or_else(|| JotError::CommandError(format!("Invalid tag '{}'", t)))
+            })
+            .collect::<JotResult<_>>()?,
+        match_type: if args.all {
+            TagMatch::All
+        } else {
+            TagMatch::Any
+        },
+        from: args.from.as_deref().map(parse_filter_date).transpose()?,
+        to: args.to.as_deref().map(parse_filter_date).transpose()?,
+        kinds: args.kinds.iter().map(|k| k.trim().to_lowercase()).collect(),
+        statuses: args.statuses.clone(),
+        unchecked: args.unchecked,
+        modified_since: args
+            .modified_since
+            .as_deref()
+            .map(parse_filter_date)
+            .transpose()?,
+        tag_cfg: Some(config.journal_cfg.clone()),
+        ..EntryFilter::default()
+    };
+
+    let where_filter = args
+        .where_query
+        .as_deref()
+        .map(|q| EntryFilter::parse(q, &config.journal_cfg))
+        .transpose()?;
+
+    let mut found: Vec<_> = entries
         .iter()
-        .filter(|e| {
-            if let Some(from) = &args.from {
-                let parsed_date = utils::parse_date(from);
-                if e.date < parsed_date {
-                    return false;
-                }
-            }
-            if let Some(to) = &args.to {
-                let parsed_date = utils::parse_date(to);
-                if e.date > parsed_date {
-                    return false;
-                }
-            }
-            let match_type = if args.all {
-                TagMatch::All
-            } else {
-                TagMatch::Any
-            };
-
-            utils::do_tags_match(
-                &args
-                    .tags
-                    .iter()
-                    .map(|t| Tag::new(t.to_string()))
-                    .collect::<Vec<_>>(),
-                &e.tags,
-                match_type,
-            )
-        })
-        .collect()
+        .filter(|e| filter.matches(e) && where_filter.as_ref().is_none_or(|f| f.matches(e)))
+        .collect();
+    filter::sort_entries(&mut found, args.sort.unwrap_or(SortKey::Id));
+    Ok(found)
+}
+
+fn parse_filter_date(date: &str) -> JotResult<NaiveDate> {
+    utils::parse_date(date)
+        .map_err(|e| JotError::CommandError(format!("Invalid date '{}': {}", date, e)))
 }
 
 pub fn execute(journal: &Journal, args: ViewArgs, config: &Config) -> JotResult<()> {
-    if let Some(id) = args.id {
-        if args.recent {
-            return Err(JotError::CommandError(
-                "Cannot specify both an ID and --recent".to_string(),
-            ));
-        }
+    if args.target.is_some() && args.recent {
+        return Err(JotError::CommandError(
+            "Cannot specify both an ID and --recent".to_string(),
+        ));
+    }
+    // A single entry or a saved search has nothing to filter; saved searches
+    // keep their options in the config
+    if args.target.is_some() && args.has_filters() {
+        return Err(JotError::CommandError(
+            "Filter and sort options can't be combined with an ID or @name".to_string(),
+        ));
+    }
 
+    if let Some(ViewTarget::Saved(name)) = &args.target {
+        let search = SearchArgs::saved(name, config)?;
+        search::execute(journal, search, config)?;
+    } else if let Some(ViewTarget::Id(id)) = args.target {
         utils::view_by_id(journal, id);
     } else if args.recent {
         view_recent(journal, config);
     } else {
-        let entries = filter_entries(journal.get_entries(), &args);
+        let entries = filter_entries(journal.get_entries(), &args, config)?;
         print_formatted_entries(&entries, config);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    #[derive(Parser)]
+    #[command(no_binary_name = true)]
+    struct Cli {
+        #[command(flatten)]
+        view: ViewArgs,
+    }
+
+    fn view(args: &[&str]) -> JotResult<()> {
+        let journal = Journal::new(PathBuf::from("test_journal.json"));
+        let args = Cli::try_parse_from(args).unwrap().view;
+        execute(&journal, args, &Config::default())
+    }
+
+    #[test]
+    fn test_rejected_options() {
+        assert!(matches!(
+            view(&["@standup", "--tags", "work"]),
+            Err(JotError::CommandError(_))
+        ));
+        assert!(matches!(
+            view(&["3", "--sort", "date"]),
+            Err(JotError::CommandError(_))
+        ));
+        assert!(matches!(
+            view(&["--tags", "!!"]),
+            Err(JotError::CommandError(message)) if message.contains("'!!'")
+        ));
+        assert!(view(&["--tags", "work"]).is_ok());
+    }
+}
@@ -0,0 +1,47 @@
+use colored::Colorize;
+
+use crate::{
+    error::{JotError, JotResult},
+    storage::{self, config::Config},
+    utils,
+};
+
+#[derive(clap::Args, Clone)]
+pub struct ViewsArgs {
+    /// Delete the saved search with this name
+    #[clap(long, value_name = "NAME")]
+    pub remove: Option<String>,
+}
+
+/// Lists saved searches with their arguments, or deletes one
+pub fn execute(args: ViewsArgs, config: &Config) -> JotResult<()> {
+    if let Some(name) = args.remove {
+        let name = name.trim_start_matches('@');
+        let mut config = storage::load_config()?;
+        if config.views.remove(name).is_none() {
+            return Err(JotError::CommandError(format!(
+                "No saved search named '{}'",
+                name
+            )));
+        }
+        storage::save_config(&config)?;
+        println!("{}", format!("Removed saved search @{}", name).green());
+        return Ok(());
+    }
+
+    if config.views.is_empty() {
+        println!("No saved searches. Save one with 'xlog search <query> --save <name>'");
+        return Ok(());
+    }
+
+    for (name, view) in &config.views {
+        let search = view
+            .search
+            .iter()
+            .map(|arg| utils::quote_arg(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("{} {}", format!("@{}", name).bold(), search.dimmed());
+    }
+    Ok(())
+}
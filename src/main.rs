@@ -11,11 +11,7 @@ fn main() {
     let config = match load_config() {
         Ok(config) => config,
         Err(e) => {
-            eprintln!(
-                "{} {}",
-                "Error:".red().bold(),
-                format!("Failed to load config - {}", e)
-            );
+            eprintln!("{} Failed to load config - {}", "Error:".red().bold(), e);
             eprintln!(
                 "{} Run 'xlog init' to create a new configuration",
                 "Tip:".cyan().bold()
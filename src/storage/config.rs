@@ -1,17 +1,106 @@
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use super::{DEFAULT_KIND, TASK_KIND};
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(default)]
     pub journal_cfg: JournalConfig,
+    /// User-defined kinds, or overrides of the built-in ones, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub kinds: BTreeMap<String, KindConfig>,
+    /// Saved searches by name, run with `xlog view @name`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub views: BTreeMap<String, SavedView>,
+    /// Other journal files by name, e.g. project journals, searched along with
+    /// this one by `xlog search --everywhere`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub journals: BTreeMap<String, PathBuf>,
+}
+
+impl Config {
+    /// Every available kind: the built-in ones, overridden and extended by the config
+    pub fn all_kinds(&self) -> BTreeMap<String, KindConfig> {
+        let mut kinds = builtin_kinds();
+        kinds.extend(
+            self.kinds
+                .iter()
+                .map(|(name, kind)| (name.to_lowercase(), kind.clone())),
+        );
+        kinds
+    }
+
+    /// Looks up a kind by name, ignoring case
+    pub fn kind(&self, name: &str) -> Option<KindConfig> {
+        self.all_kinds().remove(&name.trim().to_lowercase())
+    }
+}
+
+/// How entries of one kind are displayed and what they need when added
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct KindConfig {
+    /// Shown in listings instead of the kind's name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// Colour of the kind's label in listings, e.g. "yellow" or "bright blue"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Metadata fields every entry of this kind must have
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_fields: Vec<String>,
+    /// Tags added to every new entry of this kind
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_tags: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+/// A search saved with `xlog search --save`
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct SavedView {
+    /// Arguments to `xlog search`, e.g. `["deploy", "--tags", "work"]`
+    pub search: Vec<String>,
+}
+
+fn builtin_kinds() -> BTreeMap<String, KindConfig> {
+    let kind = |color: Option<&str>, required_fields: &[&str]| KindConfig {
+        color: color.map(str::to_string),
+        required_fields: required_fields.iter().map(|f| f.to_string()).collect(),
+        ..KindConfig::default()
+    };
+
+    BTreeMap::from([
+        (DEFAULT_KIND.to_string(), kind(None, &[])),
+        (TASK_KIND.to_string(), kind(Some("yellow"), &[])),
+        ("log".to_string(), kind(Some("blue"), &[])),
+        ("bookmark".to_string(), kind(Some("magenta"), &["url"])),
+    ])
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct JournalConfig {
     pub body_tags: bool,
     pub show_time: bool,
     #[serde(default = "default_export_dir")]
     pub export_dir: String,
+    /// Treat `#Work` and `#work` as the same tag by lowercasing all tags
+    #[serde(default)]
+    pub lowercase_tags: bool,
+    /// Unicode normalization applied to tags before they are compared or stored
+    #[serde(default)]
+    pub tag_normalization: TagNormalization,
+}
+
+/// Unicode normalization forms available for tags
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TagNormalization {
+    /// Keep tags exactly as typed
+    #[default]
+    None,
+    /// Canonical composition, so `é` typed either way is the same tag
+    Nfc,
+    /// Compatibility composition, which also folds variants like full-width letters
+    Nfkc,
 }
 
 fn default_export_dir() -> String {
@@ -27,6 +116,44 @@ mod tests {
         let config = Config::default();
         assert!(!config.journal_cfg.body_tags);
         assert!(!config.journal_cfg.show_time);
+        assert!(!config.journal_cfg.lowercase_tags);
+        assert_eq!(config.journal_cfg.tag_normalization, TagNormalization::None);
+    }
+
+    #[test]
+    fn test_config_tag_rules_are_optional() {
+        let config: Config =
+            toml::from_str("[journal_cfg]\nbody_tags = true\nshow_time = false\n").unwrap();
+        assert!(!config.journal_cfg.lowercase_tags);
+
+        let config: Config = toml::from_str(
+            "[journal_cfg]\nbody_tags = true\nshow_time = false\n\
+             lowercase_tags = true\ntag_normalization = \"nfkc\"\n",
+        )
+        .unwrap();
+        assert!(config.journal_cfg.lowercase_tags);
+        assert_eq!(config.journal_cfg.tag_normalization, TagNormalization::Nfkc);
+    }
+
+    #[test]
+    fn test_config_kinds() {
+        let config: Config = toml::from_str(
+            "[journal_cfg]\nbody_tags = false\nshow_time = false\n\n\
+             [kinds.Meeting]\nsymbol = \"M\"\nrequired_fields = [\"attendees\"]\n\n\
+             [kinds.task]\ncolor = \"red\"\ndefault_tags = [\"todo\"]\n",
+        )
+        .unwrap();
+
+        let meeting = config.kind("meeting").unwrap();
+        assert_eq!(meeting.symbol.as_deref(), Some("M"));
+        assert_eq!(meeting.required_fields, vec!["attendees".to_string()]);
+        assert_eq!(config.kind("task").unwrap().default_tags, vec!["todo"]);
+        assert_eq!(
+            config.kind("Bookmark").unwrap().required_fields,
+            vec!["url".to_string()]
+        );
+        assert!(config.kind("unknown").is_none());
+        assert_eq!(Config::default().all_kinds().len(), 4);
     }
 
     #[test]
@@ -41,4 +168,31 @@ mod tests {
         assert!(deserialized.journal_cfg.body_tags);
         assert!(deserialized.journal_cfg.show_time);
     }
+
+    #[test]
+    fn test_config_views() {
+        let mut config: Config = toml::from_str(
+            "[journal_cfg]\nbody_tags = false\nshow_time = false\n\n\
+             [views.standup]\nsearch = [\"deploy\", \"--tags\", \"work\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.views["standup"].search,
+            vec!["deploy", "--tags", "work"]
+        );
+
+        config.views.insert(
+            "weekly".to_string(),
+            SavedView {
+                search: vec!["tag:review".to_string()],
+            },
+        );
+        let serialized = toml::to_string_pretty(&config).unwrap();
+        assert!(serialized.contains("[views.weekly]"));
+        let deserialized: Config = toml::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.views, config.views);
+        assert!(!toml::to_string(&Config::default())
+            .unwrap()
+            .contains("views"));
+    }
 }
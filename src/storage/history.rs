@@ -0,0 +1,258 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use super::journal::{Entry, Journal};
+use crate::error::{JotError, JotResult};
+
+/// Extension of the history file, which is named after its journal
+const HISTORY_EXTENSION: &str = "history.json";
+/// Number of operations kept for undo
+const MAX_HISTORY: usize = 100;
+
+/// A single change to one entry, holding enough state to reverse it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Change {
+    Added(Entry),
+    Removed(Entry),
+    Updated { before: Entry, after: Entry },
+}
+
+/// All changes made by one journal-modifying command
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Operation {
+    pub timestamp: DateTime<Utc>,
+    /// The command line that made the changes, e.g. `remove --tags draft`
+    pub command: String,
+    pub changes: Vec<Change>,
+}
+
+impl Operation {
+    /// Compares the entries before and after a command and records the difference
+    pub fn from_diff(command: String, before: &[Entry], after: &[Entry]) -> Self {
+        let mut changes = Vec::new();
+
+        for old in before {
+            match after.iter().find(|e| e.id == old.id) {
+                Some(new) if new != old => changes.push(Change::Updated {
+                    before: old.clone(),
+                    after: new.clone(),
+                }),
+                Some(_) => {}
+                None => changes.push(Change::Removed(old.clone())),
+            }
+        }
+        for new in after {
+            if !before.iter().any(|e| e.id == new.id) {
+                changes.push(Change::Added(new.clone()));
+            }
+        }
+
+        Self {
+            timestamp: Utc::now(),
+            command,
+            changes,
+        }
+    }
+
+    /// Short summary of the changes, e.g. `+1 ~2 -3`
+    pub fn summary(&self) -> String {
+        let count = |f: fn(&Change) -> bool| self.changes.iter().filter(|c| f(c)).count();
+        let added = count(|c| matches!(c, Change::Added(_)));
+        let updated = count(|c| matches!(c, Change::Updated { .. }));
+        let removed = count(|c| matches!(c, Change::Removed(_)));
+
+        [("+", added), ("~", updated), ("-", removed)]
+            .iter()
+            .filter(|(_, n)| *n > 0)
+            .map(|(sign, n)| format!("{}{}", sign, n))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Reverts the changes on the journal
+    pub fn undo(&self, journal: &mut Journal) -> JotResult<()> {
+        let reversed = self
+            .changes
+            .iter()
+            .rev()
+            .map(|change| match change {
+                Change::Added(entry) => Change::Removed(entry.clone()),
+                Change::Removed(entry) => Change::Added(entry.clone()),
+                Change::Updated { before, after } => Change::Updated {
+                    before: after.clone(),
+                    after: before.clone(),
+                },
+            })
+            .collect::<Vec<_>>();
+
+        apply_changes(journal, &reversed)
+    }
+
+    /// Applies the changes on the journal again after an undo
+    pub fn redo(&self, journal: &mut Journal) -> JotResult<()> {
+        apply_changes(journal, &self.changes)
+    }
+}
+
+/// Applies the changes only if every entry is still in the expected state,
+/// so an undo never clobbers changes made in between
+fn apply_changes(journal: &mut Journal, changes: &[Change]) -> JotResult<()> {
+    let conflict = changes.iter().find_map(|change| {
+        let (id, ok) = match change {
+            Change::Added(entry) => (entry.id, journal.get_entry(entry.id).is_none()),
+            Change::Removed(entry) => (entry.id, journal.get_entry(entry.id) == Some(entry)),
+            Change::Updated { before, .. } => {
+                (before.id, journal.get_entry(before.id) == Some(before))
+            }
+        };
+        (!ok).then_some(id)
+    });
+
+    if let Some(id) = conflict {
+        return Err(JotError::CommandError(format!(
+            "Entry {} has changed since this operation, it can't be reverted",
+            id
+        )));
+    }
+
+    for change in changes {
+        match change {
+            Change::Added(entry) => journal.insert_entry(entry.clone()),
+            Change::Removed(entry) => {
+                journal.remove_entry(entry.id);
+            }
+            Change::Updated { after, .. } => journal.replace_entry(after.clone()),
+        }
+    }
+
+    Ok(())
+}
+
+/// The undo and redo stacks, stored next to the journal
+#[derive(Serialize, Deserialize, Default)]
+pub struct History {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Operations that can be undone, most recent last
+    pub done: Vec<Operation>,
+    /// Operations that were undone and can be redone, most recent last
+    pub undone: Vec<Operation>,
+}
+
+impl History {
+    pub fn path_for(journal: &Journal) -> PathBuf {
+        journal.path().with_extension(HISTORY_EXTENSION)
+    }
+
+    /// Adds a new operation; this discards anything that could be redone
+    pub fn push(&mut self, operation: Operation) {
+        self.done.push(operation);
+        self.undone.clear();
+
+        if self.done.len() > MAX_HISTORY {
+            self.done.drain(..self.done.len() - MAX_HISTORY);
+        }
+    }
+}
+
+/// Load the undo history belonging to a journal
+pub fn load_history(journal: &Journal) -> JotResult<History> {
+    let path = History::path_for(journal);
+    if !path.exists() {
+        return Ok(History {
+            path,
+            ..History::default()
+        });
+    }
+
+    let content = fs::read_to_string(&path).map_err(JotError::IoError)?;
+    let history: History = serde_json::from_str(&content).map_err(JotError::SerdeError)?;
+    Ok(History { path, ..history })
+}
+
+pub fn save_history(history: &History) -> JotResult<()> {
+    let content = serde_json::to_string_pretty(history).map_err(JotError::SerdeError)?;
+    let temp_path = history.path.with_extension("json.tmp");
+    fs::write(&temp_path, content).map_err(JotError::IoError)?;
+    fs::rename(&temp_path, &history.path).map_err(JotError::IoError)?;
+    Ok(())
+}
+
+/// Records the difference between `before` and the current journal as an
+/// operation that can be undone. Nothing is recorded if nothing changed.
+pub fn record(journal: &Journal, before: &[Entry], command: String) -> JotResult<()> {
+    let operation = Operation::from_diff(command, before, journal.entries());
+    if operation.changes.is_empty() {
+        return Ok(());
+    }
+
+    let mut history = load_history(journal)?;
+    history.push(operation);
+    save_history(&history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Tag;
+
+    fn journal_with(bodies: &[&str]) -> Journal {
+        let mut journal = Journal::new(PathBuf::from("test_journal.json"));
+        for body in bodies {
+            journal.add_entry(Entry::new(0, body.to_string(), vec![]));
+        }
+        journal
+    }
+
+    #[test]
+    fn test_diff_undo_redo() {
+        let mut journal = journal_with(&["a", "b", "c"]);
+        let before = journal.entries().clone();
+
+        journal.remove_entry(0);
+        let mut entry = journal.get_entry(1).unwrap().clone();
+        entry.tags.push(Tag::new("x".to_string()));
+        journal.update_entry(entry);
+        journal.add_entry(Entry::new(0, "d".to_string(), vec![]));
+        let after = journal.entries().clone();
+
+        let operation = Operation::from_diff("test".to_string(), &before, &after);
+        assert_eq!(operation.summary(), "+1 ~1 -1");
+
+        operation.undo(&mut journal).unwrap();
+        assert_eq!(journal.entries(), &before);
+
+        operation.redo(&mut journal).unwrap();
+        assert_eq!(journal.entries(), &after);
+    }
+
+    #[test]
+    fn test_undo_conflict_leaves_journal_untouched() {
+        let mut journal = journal_with(&["a", "b"]);
+        let before = journal.entries().clone();
+        journal.remove_entry(1);
+        let operation = Operation::from_diff("test".to_string(), &before, journal.entries());
+
+        // Another entry takes the removed ID before the undo
+        journal.insert_entry(Entry::new(1, "other".to_string(), vec![]));
+        let current = journal.entries().clone();
+
+        assert!(operation.undo(&mut journal).is_err());
+        assert_eq!(journal.entries(), &current);
+    }
+
+    #[test]
+    fn test_history_push_clears_redo() {
+        let mut history = History::default();
+        let operation = Operation::from_diff("test".to_string(), &[], &[]);
+        history.undone.push(operation.clone());
+
+        for _ in 0..MAX_HISTORY + 5 {
+            history.push(operation.clone());
+        }
+        assert!(history.undone.is_empty());
+        assert_eq!(history.done.len(), MAX_HISTORY);
+    }
+}
@@ -0,0 +1,557 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::journal::{Entry, Journal};
+use crate::error::{JotError, JotResult};
+use crate::utils::text;
+
+/// Extension of the index file, which is named after its journal so journals
+/// sharing a directory each keep their own
+const INDEX_EXTENSION: &str = "index.json";
+/// Bumped whenever the index layout or tokenizing changes; an index with
+/// another version is rebuilt
+const INDEX_VERSION: u32 = 2;
+/// How much more a word in the title or tags counts than one in the body
+const FIELD_WEIGHT: f64 = 2.0;
+
+/// How often a word occurs in an entry's body, title and tags
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Occurrences(pub u32, pub u32, pub u32);
+
+impl Occurrences {
+    /// Occurrences in the title and body, the text a search term is matched against
+    pub fn in_text(self) -> u32 {
+        self.0 + self.1
+    }
+
+    /// Occurrences with title and tag words counting more than body words
+    pub fn weight(self) -> f64 {
+        f64::from(self.0) + FIELD_WEIGHT * f64::from(self.1 + self.2)
+    }
+}
+
+/// The entries a word occurs in, by ID
+pub type Postings = BTreeMap<usize, Occurrences>;
+
+/// Encodes postings compactly as `id:body[:title:tags]` items separated by spaces
+fn encode_postings(postings: &Postings) -> String {
+    let mut encoded = String::new();
+    for (id, o) in postings {
+        if !encoded.is_empty() {
+            encoded.push(' ');
+        }
+        // Writing to a String can't fail
+        let _ = match o {
+            Occurrences(body, 0, 0) => write!(encoded, "{}:{}", id, body),
+            Occurrences(body, title, tags) => write!(encoded, "{}:{}:{}:{}", id, body, title, tags),
+        };
+    }
+    encoded
+}
+
+/// Decodes postings written by `encode_postings`, skipping malformed items
+fn decode_postings(encoded: &str) -> Postings {
+    encoded
+        .split(' ')
+        .filter_map(|item| {
+            let mut numbers = item.split(':').map(|n| n.parse::<u32>().ok());
+            let id = numbers.next()??;
+            let body = numbers.next()??;
+            let title = numbers.next().unwrap_or(Some(0))?;
+            let tags = numbers.next().unwrap_or(Some(0))?;
+            Some((id as usize, Occurrences(body, title, tags)))
+        })
+        .collect()
+}
+
+/// What the index knows about one entry
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct IndexedDocument {
+    /// Hash of the indexed text, to notice when the entry changes
+    hash: u64,
+    /// Weighted number of words
+    length: f64,
+    /// The distinct words of the entry, separated by spaces
+    words: String,
+}
+
+/// Entries a search term can match, worked out from the index
+#[derive(Debug, PartialEq)]
+pub enum Candidates {
+    /// Exactly these entries contain the term
+    Exact(HashSet<usize>),
+    /// Only these entries can contain the term; each still has to be checked
+    Possible(HashSet<usize>),
+}
+
+/// An inverted index from words to the entries containing them, stored next
+/// to the journal so searches don't have to tokenize every entry
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct SearchIndex {
+    #[serde(skip)]
+    path: PathBuf,
+    version: u32,
+    /// Modification time and size of the journal file when the index was
+    /// last synced, so a search can skip syncing when nothing changed
+    journal_stamp: Option<(u64, u64)>,
+    /// Latest modification time of the entries when the index was last
+    /// synced, which with the entry count catches a rewrite the file stamp
+    /// can't tell apart
+    #[serde(default)]
+    latest_modified: Option<DateTime<Utc>>,
+    documents: BTreeMap<usize, IndexedDocument>,
+    /// Each word with the entries it occurs in. Postings are kept encoded
+    /// and only decoded for the words a search looks at, which keeps loading
+    /// the index fast on large journals.
+    words: BTreeMap<String, String>,
+    /// Each stem with the words reduced to it
+    stems: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// FNV-1a hash of the indexed text. Unlike the standard library's hasher it
+/// is the same on every platform and Rust version, so it can be stored.
+fn content_hash(entry: &Entry) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let title = entry.title.as_deref().map(str::as_bytes);
+    let tags = entry.tags.iter().map(|t| t.name.as_bytes());
+    // 0xff never occurs in UTF-8, so it keeps the parts apart
+    let parts = [title.unwrap_or(&[0xfe]), entry.body.as_bytes()]
+        .into_iter()
+        .chain(tags);
+    parts
+        .flat_map(|part| part.iter().chain(&[0xff]))
+        .fold(OFFSET, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+        })
+}
+
+/// Modification time in nanoseconds and size of the journal file, or `None`
+/// if it hasn't been written yet
+fn journal_stamp(path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((u64::try_from(modified.as_nanos()).ok()?, metadata.len()))
+}
+
+/// When the most recently changed entry was last modified
+fn latest_modified(entries: &[Entry]) -> Option<DateTime<Utc>> {
+    entries.iter().map(Entry::last_modified).max()
+}
+
+/// How often each word occurs in the body, title and tags of the entry
+fn count_words(entry: &Entry) -> BTreeMap<String, Occurrences> {
+    let mut counts: BTreeMap<String, Occurrences> = BTreeMap::new();
+    for word in text::tokenize(&entry.body) {
+        counts.entry(word).or_default().0 += 1;
+    }
+    for word in entry.title.iter().flat_map(|t| text::tokenize(t)) {
+        counts.entry(word).or_default().1 += 1;
+    }
+    for word in entry.tags.iter().flat_map(|t| text::tokenize(&t.name)) {
+        counts.entry(word).or_default().2 += 1;
+    }
+    counts
+}
+
+impl SearchIndex {
+    pub fn path_for(journal: &Journal) -> PathBuf {
+        journal.path().with_extension(INDEX_EXTENSION)
+    }
+
+    /// Indexes the entries from scratch
+    pub fn build(entries: &[Entry]) -> Self {
+        let mut index = Self {
+            version: INDEX_VERSION,
+            ..Self::default()
+        };
+        index.sync(entries);
+        index
+    }
+
+    /// Brings the index up to date with the entries, re-indexing only those
+    /// that were added, changed or removed since.
+    ///
+    /// # Returns
+    ///
+    /// True if anything changed.
+    pub fn sync(&mut self, entries: &[Entry]) -> bool {
+        let hashes = entries
+            .iter()
+            .map(|e| (e.id, content_hash(e)))
+            .collect::<HashMap<_, _>>();
+        let stale = self
+            .documents
+            .iter()
+            .filter(|(id, document)| hashes.get(id) != Some(&document.hash))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        let mut changed: HashMap<String, Postings> = HashMap::new();
+
+        for id in &stale {
+            let Some(document) = self.documents.remove(id) else {
+                continue;
+            };
+            for word in document.words.split(' ').filter(|w| !w.is_empty()) {
+                if !changed.contains_key(word) {
+                    let postings = self.postings(word).unwrap_or_default();
+                    changed.insert(word.to_string(), postings);
+                }
+                if let Some(postings) = changed.get_mut(word) {
+                    postings.remove(id);
+                }
+            }
+        }
+
+        let added = entries
+            .iter()
+            .filter(|e| !self.documents.contains_key(&e.id))
+            .collect::<Vec<_>>();
+        for entry in &added {
+            let counts = count_words(entry);
+            for (word, occurrences) in &counts {
+                match changed.get_mut(word) {
+                    Some(postings) => {
+                        postings.insert(entry.id, *occurrences);
+                    }
+                    None => {
+                        let mut postings = self.postings(word).unwrap_or_default();
+                        postings.insert(entry.id, *occurrences);
+                        changed.insert(word.clone(), postings);
+                    }
+                }
+            }
+
+            self.documents.insert(
+                entry.id,
+                IndexedDocument {
+                    hash: hashes[&entry.id],
+                    length: counts.values().map(|o| o.weight()).sum(),
+                    words: counts.into_keys().collect::<Vec<_>>().join(" "),
+                },
+            );
+        }
+
+        // Write back every word whose postings changed, forgetting unused ones
+        for (word, postings) in changed {
+            let stem = text::stem(&word);
+            if !postings.is_empty() {
+                self.words.insert(word.clone(), encode_postings(&postings));
+                self.stems.entry(stem).or_default().insert(word);
+                continue;
+            }
+
+            self.words.remove(&word);
+            if let Some(words) = self.stems.get_mut(&stem) {
+                words.remove(&word);
+                if words.is_empty() {
+                    self.stems.remove(&stem);
+                }
+            }
+        }
+
+        !stale.is_empty() || !added.is_empty()
+    }
+
+    /// Number of indexed entries
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Number of distinct words
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Average weighted number of words per entry
+    pub fn average_length(&self) -> f64 {
+        if self.documents.is_empty() {
+            return 0.0;
+        }
+        self.documents.values().map(|d| d.length).sum::<f64>() / self.documents.len() as f64
+    }
+
+    /// Weighted number of words of an entry
+    pub fn length(&self, id: usize) -> f64 {
+        self.documents.get(&id).map_or(0.0, |d| d.length)
+    }
+
+    /// The entries a word occurs in
+    pub fn postings(&self, word: &str) -> Option<Postings> {
+        self.words.get(word).map(|encoded| decode_postings(encoded))
+    }
+
+    /// Words with the given stem
+    pub fn words_with_stem<'a>(&'a self, stem: &str) -> impl Iterator<Item = &'a str> {
+        self.stems
+            .get(stem)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// Words starting with the given prefix
+    pub fn words_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a str> {
+        self.words
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .map(|(word, _)| word.as_str())
+            .take_while(move |word| word.starts_with(prefix))
+    }
+
+    /// Entries with one of the words in their title or body
+    fn text_postings<'a>(&self, words: impl Iterator<Item = &'a str>) -> HashSet<usize> {
+        words
+            .filter_map(|word| self.postings(word))
+            .flatten()
+            .filter(|(_, occurrences)| occurrences.in_text() > 0)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Entries containing a word that starts with `part` or has the same stem
+    fn word_matches(&self, part: &str) -> HashSet<usize> {
+        let stem = text::stem(part);
+        let mut ids = self.text_postings(self.words_with_prefix(part));
+        ids.extend(self.text_postings(self.words_with_stem(&stem)));
+        ids
+    }
+
+    /// Works out which entries a case-insensitive search term can match, the
+    /// same way `query::text_matches` does without an index.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the index can't narrow the term down, e.g. if it has no words.
+    pub fn text_candidates(&self, term: &str) -> Option<Candidates> {
+        let term = term.to_lowercase();
+        if let Some(prefix) = term.strip_suffix('*') {
+            return match text::tokenize(prefix).as_slice() {
+                [word] if word == prefix => Some(Candidates::Exact(
+                    self.text_postings(self.words_with_prefix(prefix)),
+                )),
+                _ => None,
+            };
+        }
+
+        let words = text::tokenize(&term);
+        match words.as_slice() {
+            [] => None,
+            [word] if *word == term => Some(Candidates::Exact(self.word_matches(word))),
+            // A phrase can start in the middle of a word, so only the words
+            // after the first narrow it down
+            [_, rest @ ..] => rest
+                .iter()
+                .map(|word| self.word_matches(word))
+                .reduce(|a, b| a.intersection(&b).copied().collect())
+                .map(Candidates::Possible),
+        }
+    }
+}
+
+/// Load the search index belonging to a journal and bring it up to date with
+/// the entries, saving it if anything changed. A missing, unreadable or
+/// outdated index is rebuilt. The entries are only compared with the index
+/// when the journal file, the number of entries or their latest modification
+/// time changed since the index was last synced.
+pub fn update_index(journal: &Journal) -> JotResult<SearchIndex> {
+    let path = SearchIndex::path_for(journal);
+    let loaded = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<SearchIndex>(&content).ok())
+        .filter(|index| index.version == INDEX_VERSION);
+
+    let (mut index, mut changed) = match loaded {
+        Some(index) => (index, false),
+        None => (SearchIndex::build(&[]), true),
+    };
+    index.path = path;
+
+    let stamp = journal_stamp(journal.path());
+    let latest = latest_modified(journal.entries());
+    if stamp.is_some()
+        && stamp == index.journal_stamp
+        && latest == index.latest_modified
+        && journal.entries().len() == index.len()
+    {
+        return Ok(index);
+    }
+    changed |= index.sync(journal.entries());
+    changed |= index.journal_stamp != stamp || index.latest_modified != latest;
+    index.journal_stamp = stamp;
+    index.latest_modified = latest;
+
+    if changed {
+        save_index(&index)?;
+    }
+    Ok(index)
+}
+
+/// Rebuilds the search index of a journal from scratch and saves it
+pub fn rebuild_index(journal: &Journal) -> JotResult<SearchIndex> {
+    let index = SearchIndex {
+        path: SearchIndex::path_for(journal),
+        journal_stamp: journal_stamp(journal.path()),
+        latest_modified: latest_modified(journal.entries()),
+        ..SearchIndex::build(journal.entries())
+    };
+    save_index(&index)?;
+    Ok(index)
+}
+
+pub fn save_index(index: &SearchIndex) -> JotResult<()> {
+    let content = serde_json::to_string(index).map_err(JotError::SerdeError)?;
+    let temp_path = index.path.with_extension("json.tmp");
+    fs::write(&temp_path, content).map_err(JotError::IoError)?;
+    fs::rename(&temp_path, &index.path).map_err(JotError::IoError)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Tag;
+
+    fn entries() -> Vec<Entry> {
+        let mut titled = Entry::new(1, "call notes".to_string(), vec![]);
+        titled.title = Some("Deploy plan".to_string());
+        vec![
+            Entry::new(0, "Deployed the API".to_string(), vec![]),
+            titled,
+            Entry::new(
+                2,
+                "lunch".to_string(),
+                vec![Tag::new("deploys".to_string())],
+            ),
+        ]
+    }
+
+    fn ids(candidates: Option<Candidates>) -> Vec<usize> {
+        let ids = match candidates {
+            Some(Candidates::Exact(ids) | Candidates::Possible(ids)) => ids,
+            None => return Vec::new(),
+        };
+        let mut ids = ids.into_iter().collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn test_text_candidates() {
+        let index = SearchIndex::build(&entries());
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.postings("deploy").unwrap()[&1], Occurrences(0, 1, 0));
+
+        // Tags aren't part of the text a term is matched against
+        assert_eq!(ids(index.text_candidates("DEPLOYING")), vec![0, 1]);
+        assert_eq!(ids(index.text_candidates("ap")), vec![0]);
+        assert_eq!(ids(index.text_candidates("pi")), Vec::<usize>::new());
+        assert_eq!(ids(index.text_candidates("lun*")), vec![2]);
+        assert!(matches!(
+            index.text_candidates("the api"),
+            Some(Candidates::Possible(_))
+        ));
+        assert_eq!(ids(index.text_candidates("the api")), vec![0]);
+        assert_eq!(ids(index.text_candidates("ployed the")), vec![0]);
+        assert_eq!(index.text_candidates("!!"), None);
+    }
+
+    #[test]
+    fn test_encode_postings() {
+        let postings = Postings::from([(3, Occurrences(2, 0, 0)), (10, Occurrences(0, 1, 1))]);
+        let encoded = encode_postings(&postings);
+        assert_eq!(encoded, "3:2 10:0:1:1");
+        assert_eq!(decode_postings(&encoded), postings);
+        assert_eq!(
+            decode_postings("1:x 2:1"),
+            Postings::from([(2, Occurrences(1, 0, 0))])
+        );
+    }
+
+    #[test]
+    fn test_sync() {
+        let mut entries = entries();
+        let mut index = SearchIndex::build(&entries);
+        assert!(!index.sync(&entries));
+
+        entries[0].body = "Rolled back".to_string();
+        entries.remove(2);
+        entries.push(Entry::new(3, "new entry".to_string(), vec![]));
+        assert!(index.sync(&entries));
+
+        assert_eq!(index, SearchIndex::build(&entries));
+        assert!(index.postings("lunch").is_none());
+        assert_eq!(
+            index.words_with_stem("deploy").collect::<Vec<_>>(),
+            vec!["deploy"]
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        // Stored in the index, so it must never change between builds
+        let mut entry = Entry::new(0, "body".to_string(), vec![Tag::new("tag".to_string())]);
+        assert_eq!(content_hash(&entry), 0xfeef_8c98_3593_2e8a);
+        entry.title = Some(String::new());
+        assert_ne!(content_hash(&entry), 0xfeef_8c98_3593_2e8a);
+    }
+
+    #[test]
+    fn test_update_index() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut journal = Journal::new(temp_dir.path().join("journal.json"));
+        journal.add_entry(Entry::new(0, "first".to_string(), vec![]));
+
+        let index = update_index(&journal).unwrap();
+        assert!(SearchIndex::path_for(&journal).exists());
+        assert!(index.postings("first").is_some());
+
+        journal.add_entry(Entry::new(1, "second".to_string(), vec![]));
+        update_index(&journal).unwrap();
+        let content = fs::read_to_string(SearchIndex::path_for(&journal)).unwrap();
+        let saved: SearchIndex = serde_json::from_str(&content).unwrap();
+        assert_eq!(saved.len(), 2);
+
+        // Journals in the same directory keep separate indexes
+        let other = Journal::new(temp_dir.path().join("work.json"));
+        assert_ne!(
+            SearchIndex::path_for(&other),
+            SearchIndex::path_for(&journal)
+        );
+    }
+
+    #[test]
+    fn test_update_index_skips_unchanged_journal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut journal = Journal::new(temp_dir.path().join("journal.json"));
+        journal.add_entry(Entry::new(0, "first".to_string(), vec![]));
+        crate::storage::save_journal(&journal).unwrap();
+
+        let path = SearchIndex::path_for(&journal);
+        let before = fs::metadata(&path).unwrap().modified().unwrap();
+
+        // Nothing changed, so the entries aren't compared and nothing is written
+        let index = update_index(&journal).unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), before);
+
+        // A different entry count is synced even though the file stamp matches
+        journal.add_entry(Entry::new(1, "second".to_string(), vec![]));
+        let index = update_index(&journal).unwrap();
+        assert_eq!(index.len(), 2);
+
+        // So is an edit that leaves the count alone
+        let mut edited = journal.get_entry(1).unwrap().clone();
+        edited.body = "changed".to_string();
+        edited.modified_at = Some(Utc::now() + chrono::Duration::seconds(1));
+        journal.replace_entry(edited);
+        let index = update_index(&journal).unwrap();
+        assert!(index.postings("changed").is_some());
+    }
+}
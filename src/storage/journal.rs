@@ -1,8 +1,111 @@
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+/// Separates the levels of a nested tag such as `work/clientA/billing`
+pub const TAG_SEPARATOR: char = '/';
+
+/// Kind of entries added without `--kind`, and of entries from before kinds existed
+pub const DEFAULT_KIND: &str = "note";
+
+/// Kind of entries that carry a task status and priority
+pub const TASK_KIND: &str = "task";
+
+fn default_kind() -> String {
+    DEFAULT_KIND.to_string()
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskStatus {
+    #[default]
+    Open,
+    InProgress,
+    Done,
+    Cancelled,
+}
+
+impl TaskStatus {
+    /// Returns true for tasks that still need doing
+    pub fn is_open(self) -> bool {
+        matches!(self, TaskStatus::Open | TaskStatus::InProgress)
+    }
+}
+
+impl std::fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TaskStatus::Open => "open",
+            TaskStatus::InProgress => "in-progress",
+            TaskStatus::Done => "done",
+            TaskStatus::Cancelled => "cancelled",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Debug,
+    Default,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// State of an entry of the task kind
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct Task {
+    pub status: TaskStatus,
+    pub priority: Priority,
+    /// When the task was marked done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Date the task has to be done by
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+}
+
+impl Task {
+    /// Changes the status, recording the completion time when the task gets done
+    pub fn set_status(&mut self, status: TaskStatus) {
+        match status {
+            TaskStatus::Done if self.status != TaskStatus::Done => {
+                self.completed_at = Some(Utc::now())
+            }
+            TaskStatus::Done => {}
+            _ => self.completed_at = None,
+        }
+        self.status = status;
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Hash, Eq)]
 pub struct Tag {
     pub name: String,
@@ -13,8 +116,31 @@ impl Tag {
         Self { name }
     }
 
-    pub fn from_hash(s: &str) -> Self {
-        Self::new(s.trim_start_matches('#').to_string())
+    /// Returns true if this tag is `ancestor` itself or nested below it,
+    /// e.g. `work/clientA/billing` is within `work` and `work/clientA`.
+    pub fn is_within(&self, ancestor: &Tag) -> bool {
+        self.name
+            .strip_prefix(&ancestor.name)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(TAG_SEPARATOR))
+    }
+
+    /// Moves this tag from below `old` to below `new`, keeping the nested part.
+    /// Returns `None` if the tag is not within `old`.
+    pub fn reparent(&self, old: &Tag, new: &Tag) -> Option<Tag> {
+        if !self.is_within(old) {
+            return None;
+        }
+
+        Some(Tag::new(format!(
+            "{}{}",
+            new.name,
+            &self.name[old.name.len()..]
+        )))
+    }
+
+    /// The path segments of a nested tag, e.g. `["work", "clientA"]` for `work/clientA`
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.name.split(TAG_SEPARATOR).filter(|s| !s.is_empty())
     }
 }
 
@@ -38,23 +164,80 @@ impl AsRef<str> for Tag {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Entry {
     pub id: usize,
     pub timestamp: DateTime<Utc>,
     pub date: NaiveDate,
+    /// When the entry was last changed; missing for entries never changed
+    /// since before modification times were recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<DateTime<Utc>>,
+    /// Explicit title; entries without one use the first line of the body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
     pub body: String,
     pub tags: Vec<Tag>,
+    /// Name of a built-in or configured kind, e.g. `task` or `bookmark`
+    #[serde(default = "default_kind")]
+    pub kind: String,
+    /// Status and priority, present exactly for entries of the task kind
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<Task>,
+    /// Structured metadata such as `hours: 2.5`, sorted by key
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
 }
 
 impl Entry {
     pub fn new(id: usize, body: String, tags: Vec<Tag>) -> Self {
+        let now = Utc::now();
         Self {
             id,
-            timestamp: Utc::now(),
-            date: Utc::now().naive_utc().date(),
+            timestamp: now,
+            modified_at: Some(now),
+            date: now.naive_utc().date(),
+            title: None,
             body,
             tags,
+            kind: default_kind(),
+            task: None,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// When the entry was last changed, falling back to its creation time
+    pub fn last_modified(&self) -> DateTime<Utc> {
+        self.modified_at.unwrap_or(self.timestamp)
+    }
+
+    /// Returns true for open tasks due before `today`
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.task
+            .as_ref()
+            .is_some_and(|t| t.status.is_open() && t.due.is_some_and(|due| due < today))
+    }
+
+    /// Adds or drops the task state after the kind changed, so that exactly
+    /// task entries have one
+    pub fn sync_task(&mut self) {
+        if self.kind != TASK_KIND {
+            self.task = None;
+        } else if self.task.is_none() {
+            self.task = Some(Task::default());
+        }
+    }
+
+    /// The explicit title, or the first non-empty line of the body
+    pub fn display_title(&self) -> &str {
+        match &self.title {
+            Some(title) => title,
+            None => self
+                .body
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .unwrap_or_default(),
         }
     }
 }
@@ -62,6 +245,17 @@ impl Entry {
 pub struct Journal {
     path: PathBuf,
     entries: Vec<Entry>,
+    /// One more than the highest ID ever handed out, kept in the journal file
+    /// so the IDs of removed entries aren't reused
+    next_id: usize,
+}
+
+/// The journal file: the entries with the next ID to hand out. Older journal
+/// files hold only the array of entries.
+#[derive(Serialize, Deserialize)]
+pub struct JournalFile<'a> {
+    pub next_id: usize,
+    pub entries: Cow<'a, [Entry]>,
 }
 
 impl Journal {
@@ -69,11 +263,33 @@ impl Journal {
         Self {
             path,
             entries: Vec::new(),
+            next_id: 0,
         }
     }
 
     pub fn from_entries(path: PathBuf, entries: Vec<Entry>) -> Self {
-        Self { path, entries }
+        Self {
+            path,
+            entries,
+            next_id: 0,
+        }
+    }
+
+    /// Loads a journal from its file, keeping the stored next ID
+    pub fn from_file(path: PathBuf, file: JournalFile<'_>) -> Self {
+        Self {
+            path,
+            entries: file.entries.into_owned(),
+            next_id: file.next_id,
+        }
+    }
+
+    /// The journal as it is written to its file
+    pub fn to_file(&self) -> JournalFile<'_> {
+        JournalFile {
+            next_id: self.next_id(),
+            entries: Cow::Borrowed(&self.entries),
+        }
     }
 
     pub fn path(&self) -> &PathBuf {
@@ -85,10 +301,22 @@ impl Journal {
     }
 
     pub fn add_entry(&mut self, entry: Entry) {
-        let id = self.entries.len();
+        let id = self.next_id();
         let entry = Entry { id, ..entry };
 
         self.entries.push(entry);
+        self.next_id = id + 1;
+    }
+
+    /// Inserts an entry keeping its ID, e.g. to restore a removed entry
+    pub fn insert_entry(&mut self, entry: Entry) {
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.id > entry.id)
+            .unwrap_or(self.entries.len());
+        self.next_id = self.next_id.max(entry.id + 1);
+        self.entries.insert(index, entry);
     }
 
     pub fn remove_entry(&mut self, id: usize) -> Option<Entry> {
@@ -99,7 +327,22 @@ impl Journal {
         }
     }
 
+    /// Removes every entry whose ID is in `ids` and returns the removed entries
+    pub fn remove_entries(&mut self, ids: &[usize]) -> Vec<Entry> {
+        ids.iter().filter_map(|id| self.remove_entry(*id)).collect()
+    }
+
+    /// Replaces the entry with the same ID and marks it as modified now
     pub fn update_entry(&mut self, entry: Entry) {
+        self.replace_entry(Entry {
+            modified_at: Some(Utc::now()),
+            ..entry
+        });
+    }
+
+    /// Replaces the entry with the same ID exactly as given, e.g. to restore
+    /// an earlier version of it
+    pub fn replace_entry(&mut self, entry: Entry) {
         if let Some(index) = self.entries.iter().position(|e| e.id == entry.id) {
             self.entries[index] = entry;
         }
@@ -113,8 +356,14 @@ impl Journal {
         &self.entries
     }
 
+    /// IDs are never reused, so removed entries can be restored under their old ID
     pub fn next_id(&self) -> usize {
-        self.entries.len()
+        self.entries
+            .iter()
+            .map(|e| e.id + 1)
+            .max()
+            .unwrap_or(0)
+            .max(self.next_id)
     }
 }
 
@@ -122,6 +371,27 @@ impl Journal {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_task_status() {
+        let mut entry = Entry::new(0, "Ship it".to_string(), vec![]);
+        entry.kind = TASK_KIND.to_string();
+        entry.sync_task();
+
+        let task = entry.task.as_mut().unwrap();
+        assert!(task.status.is_open());
+        task.set_status(TaskStatus::Done);
+        let completed = task.completed_at;
+        assert!(completed.is_some());
+        task.set_status(TaskStatus::Done);
+        assert_eq!(task.completed_at, completed);
+        task.set_status(TaskStatus::Open);
+        assert_eq!(task.completed_at, None);
+
+        entry.kind = DEFAULT_KIND.to_string();
+        entry.sync_task();
+        assert_eq!(entry.task, None);
+    }
+
     #[test]
     fn test_entry_creation() {
         let entry = Entry::new(
@@ -135,6 +405,42 @@ mod tests {
         assert!(entry.timestamp <= Utc::now());
     }
 
+    #[test]
+    fn test_nested_tags() {
+        let tag = Tag::new("work/clientA/billing".to_string());
+        let work = Tag::new("work".to_string());
+
+        assert!(tag.is_within(&work));
+        assert!(tag.is_within(&Tag::new("work/clientA".to_string())));
+        assert!(tag.is_within(&tag));
+        assert!(!tag.is_within(&Tag::new("wor".to_string())));
+        assert!(!work.is_within(&tag));
+
+        assert_eq!(
+            tag.reparent(&work, &Tag::new("job".to_string())),
+            Some(Tag::new("job/clientA/billing".to_string()))
+        );
+        assert_eq!(work.reparent(&tag, &work), None);
+        assert_eq!(
+            tag.segments().collect::<Vec<_>>(),
+            vec!["work", "clientA", "billing"]
+        );
+    }
+
+    #[test]
+    fn test_entry_title() {
+        let mut entry = Entry::new(0, "\n  First line\nSecond line".to_string(), vec![]);
+        assert_eq!(entry.display_title(), "First line");
+
+        entry.title = Some("Explicit".to_string());
+        assert_eq!(entry.display_title(), "Explicit");
+
+        // Entries saved before titles existed still load
+        let json = r#"{"id":0,"timestamp":"2025-01-01T00:00:00Z","date":"2025-01-01","body":"b","tags":[]}"#;
+        let entry: Entry = serde_json::from_str(json).unwrap();
+        assert_eq!(entry.title, None);
+    }
+
     #[test]
     fn test_entry_creation_with_details() {
         let body = "Test entry".to_string();
@@ -176,8 +482,11 @@ mod tests {
         // Test updating entries
         let mut entry = journal.get_entry(1).unwrap().clone();
         entry.body = "Updated entry".to_string();
+        let created = entry.timestamp;
         journal.update_entry(entry);
         assert_eq!(journal.get_entry(1).unwrap().body, "Updated entry");
+        assert_eq!(journal.get_entry(1).unwrap().timestamp, created);
+        assert!(journal.get_entry(1).unwrap().last_modified() >= created);
     }
 
     #[test]
@@ -220,6 +529,21 @@ mod tests {
         assert_eq!(updated.tags, vec![Tag::new("updated".to_string())]);
     }
 
+    #[test]
+    fn test_remove_entries() {
+        let mut journal = Journal::new(PathBuf::from("test_journal.json"));
+        for i in 0..4 {
+            journal.add_entry(Entry::new(0, format!("Entry {}", i), vec![]));
+        }
+
+        let removed = journal.remove_entries(&[1, 3, 7]);
+        assert_eq!(removed.iter().map(|e| e.id).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(
+            journal.entries().iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+    }
+
     #[test]
     fn test_journal_edge_cases() {
         let path = PathBuf::from("test_journal.json");
@@ -273,5 +597,21 @@ mod tests {
         assert_eq!(journal.next_id(), 0);
         journal.add_entry(Entry::new(0, "Entry".to_string(), vec![]));
         assert_eq!(journal.next_id(), 1);
+
+        // IDs of removed entries are not handed out again
+        journal.add_entry(Entry::new(0, "Entry".to_string(), vec![]));
+        journal.remove_entry(0);
+        assert_eq!(journal.next_id(), 2);
+        journal.insert_entry(Entry::new(0, "Restored".to_string(), vec![]));
+        assert_eq!(journal.entries()[0].body, "Restored");
+
+        // Nor is the ID of the highest entry once it is removed
+        journal.remove_entry(1);
+        assert_eq!(journal.next_id(), 2);
+        journal.add_entry(Entry::new(0, "New".to_string(), vec![]));
+        assert_eq!(journal.entries()[1].id, 2);
+        journal.remove_entry(2);
+        journal.remove_entry(0);
+        assert_eq!(journal.next_id(), 3);
     }
 }
@@ -1,7 +1,11 @@
 pub mod config;
+pub mod history;
+pub mod index;
 pub mod journal;
 
-pub use journal::{Entry, Journal, Tag};
+pub use journal::{
+    Entry, Journal, JournalFile, Priority, Tag, TaskStatus, DEFAULT_KIND, TAG_SEPARATOR, TASK_KIND,
+};
 use serde::de::Error;
 
 use crate::error::{JotError, JotResult};
@@ -35,13 +39,18 @@ impl Backup {
     }
 
     pub fn create(&self) -> JotResult<()> {
+        // Nothing to back up before the journal has been written for the first time
+        if !self.source_path.exists() {
+            return Ok(());
+        }
+
         if self.backup_path.exists() {
             fs::rename(&self.backup_path, &self.old_backup_path)
-                .map_err(|e| JotError::Other(format!("Failed to rename backup: {}", e).into()))?;
+                .map_err(|e| JotError::BackupError(format!("Failed to rename backup: {}", e)))?;
         }
 
         fs::copy(&self.source_path, &self.backup_path)
-            .map_err(|e| JotError::Other(format!("Failed to create backup: {}", e).into()))?;
+            .map_err(|e| JotError::BackupError(format!("Failed to create backup: {}", e)))?;
 
         Ok(())
     }
@@ -49,9 +58,9 @@ impl Backup {
     pub fn restore(&self) -> JotResult<()> {
         if self.backup_path.exists() {
             fs::copy(&self.backup_path, &self.source_path)
-                .map_err(|e| JotError::Other(format!("Failed to restore backup: {}", e).into()))?;
+                .map_err(|e| JotError::BackupError(format!("Failed to restore backup: {}", e)))?;
         } else {
-            return Err(JotError::Other("Backup not found".into()));
+            return Err(JotError::BackupError("Backup not found".to_string()));
         }
 
         Ok(())
@@ -76,27 +85,49 @@ pub fn load_journal() -> JotResult<Journal> {
 pub fn load_from_path(path: PathBuf) -> JotResult<Journal> {
     match fs::read_to_string(&path) {
         Ok(content) => {
-            // Validate JSON structure before parsing
-            if !content.trim().starts_with('[') || !content.trim().ends_with(']') {
+            // Validate JSON structure before parsing; older journals are a
+            // bare array of entries
+            let content = content.trim();
+            if content.starts_with('[') && content.ends_with(']') {
+                let entries: Vec<Entry> =
+                    serde_json::from_str(content).map_err(JotError::SerdeError)?;
+                return Ok(Journal::from_entries(path, entries));
+            }
+            if !content.starts_with('{') || !content.ends_with('}') {
                 return Err(JotError::Other("Invalid journal file format".into()));
             }
 
-            let entries: Vec<Entry> =
-                serde_json::from_str(&content).map_err(JotError::SerdeError)?;
-            Ok(Journal::from_entries(path, entries))
+            let file: JournalFile = serde_json::from_str(content).map_err(JotError::SerdeError)?;
+            Ok(Journal::from_file(path, file))
         }
         Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Journal::new(path)),
         Err(e) => Err(JotError::IoError(e)),
     }
 }
 
+/// Load every journal registered in the config's `[journals]` table, with its name
+pub fn load_named_journals(config: &Config) -> JotResult<Vec<(String, Journal)>> {
+    config
+        .journals
+        .iter()
+        .map(|(name, path)| {
+            if !path.exists() {
+                return Err(JotError::Other(
+                    format!("Journal '{}' not found at {}", name, path.display()).into(),
+                ));
+            }
+            Ok((name.clone(), load_from_path(path.clone())?))
+        })
+        .collect()
+}
+
 pub fn save_journal(journal: &Journal) -> JotResult<()> {
     let backup = Backup::from_journal(journal);
     backup.create()?;
 
     // Serialize entries
     let serialized_entries =
-        serde_json::to_string_pretty(journal.entries()).map_err(JotError::SerdeError)?;
+        serde_json::to_string_pretty(&journal.to_file()).map_err(JotError::SerdeError)?;
 
     // Write to temporary file first
     let temp_path = journal.path().with_extension("json.tmp");
@@ -111,6 +142,9 @@ pub fn save_journal(journal: &Journal) -> JotResult<()> {
     // Atomically rename temporary file to actual journal file
     fs::rename(&temp_path, journal.path()).map_err(JotError::IoError)?;
 
+    // Re-index the entries that changed
+    index::update_index(journal)?;
+
     Ok(())
 }
 
@@ -276,6 +310,46 @@ mod tests {
         assert_eq!(loaded_journal.entries()[0].body, "Test entry");
     }
 
+    #[test]
+    fn test_next_id_survives_reload() {
+        let (_temp_dir, journal_path, _) = setup_test_env();
+        let mut journal = Journal::new(journal_path.clone());
+        journal.add_entry(Entry::new(0, "First".to_string(), vec![]));
+        journal.add_entry(Entry::new(0, "Second".to_string(), vec![]));
+        journal.remove_entry(1);
+        save_journal(&journal).unwrap();
+
+        // Removing the highest entry doesn't free its ID
+        let mut journal = load_from_path(journal_path.clone()).unwrap();
+        assert_eq!(journal.next_id(), 2);
+        journal.add_entry(Entry::new(0, "Third".to_string(), vec![]));
+        assert_eq!(journal.entries()[1].id, 2);
+
+        // Journals written as a bare array still load
+        fs::write(&journal_path, "[]").unwrap();
+        assert_eq!(load_from_path(journal_path).unwrap().next_id(), 0);
+    }
+
+    #[test]
+    fn test_load_named_journals() {
+        let (temp_dir, journal_path, _) = setup_test_env();
+        let mut journal = Journal::new(journal_path.clone());
+        journal.add_entry(Entry::new(0, "Project entry".to_string(), vec![]));
+        save_journal(&journal).unwrap();
+
+        let mut config = Config::default();
+        config.journals.insert("project".to_string(), journal_path);
+        let journals = load_named_journals(&config).unwrap();
+        assert_eq!(journals.len(), 1);
+        assert_eq!(journals[0].0, "project");
+        assert_eq!(journals[0].1.entries()[0].body, "Project entry");
+
+        config
+            .journals
+            .insert("gone".to_string(), temp_dir.path().join("missing.json"));
+        assert!(load_named_journals(&config).is_err());
+    }
+
     #[test]
     fn test_config_operations() {
         let (_temp_dir, _, config_path) = setup_test_env();
@@ -0,0 +1,148 @@
+/// One `- [ ] item` line of an entry body
+#[derive(Debug, PartialEq)]
+pub struct ChecklistItem {
+    /// Position among the checklist items, starting at 1
+    pub index: usize,
+    /// Line of the body the item is on, starting at 0
+    pub line: usize,
+    pub checked: bool,
+    pub text: String,
+}
+
+/// Splits a checklist line into the part up to and including `[`, the
+/// checked state and the text after `]`
+fn parse_line(line: &str) -> Option<(&str, bool, &str)> {
+    let trimmed = line.trim_start();
+    let rest = trimmed
+        .strip_prefix("- [")
+        .or_else(|| trimmed.strip_prefix("* ["))?;
+    let checked = match rest.chars().next()? {
+        ' ' => false,
+        'x' | 'X' => true,
+        _ => return None,
+    };
+    let text = rest[1..].strip_prefix(']')?;
+    if !text.is_empty() && !text.starts_with(' ') {
+        return None;
+    }
+
+    let prefix_len = line.len() - rest.len();
+    Some((&line[..prefix_len], checked, text.trim()))
+}
+
+/// Collects the Markdown checklist items (`- [ ] todo`, `- [x] done`) of a body
+pub fn parse_checklist(body: &str) -> Vec<ChecklistItem> {
+    body.lines()
+        .enumerate()
+        .filter_map(|(line, text)| parse_line(text).map(|(_, checked, text)| (line, checked, text)))
+        .enumerate()
+        .map(|(i, (line, checked, text))| ChecklistItem {
+            index: i + 1,
+            line,
+            checked,
+            text: text.to_string(),
+        })
+        .collect()
+}
+
+/// Number of checked items and of all items, or `None` without a checklist
+pub fn progress(body: &str) -> Option<(usize, usize)> {
+    let items = parse_checklist(body);
+    (!items.is_empty()).then(|| (items.iter().filter(|i| i.checked).count(), items.len()))
+}
+
+/// Returns true if the body has checklist items that aren't checked yet
+pub fn has_unchecked(body: &str) -> bool {
+    parse_checklist(body).iter().any(|i| !i.checked)
+}
+
+/// Finds an item by its index or by a unique, case-insensitive part of its text
+///
+/// # Errors
+///
+/// Returns a message for the caller to wrap if no item or several items match.
+pub fn find_item<'a>(items: &'a [ChecklistItem], query: &str) -> Result<&'a ChecklistItem, String> {
+    if let Ok(index) = query.trim().parse::<usize>() {
+        return items
+            .iter()
+            .find(|i| i.index == index)
+            .ok_or_else(|| format!("There is no checklist item {}", index));
+    }
+
+    let query = query.to_lowercase();
+    let matching = items
+        .iter()
+        .filter(|i| i.text.to_lowercase().contains(&query))
+        .collect::<Vec<_>>();
+    match matching.as_slice() {
+        [item] => Ok(item),
+        [] => Err(format!("No checklist item matches '{}'", query)),
+        _ => Err(format!(
+            "'{}' matches {} checklist items, use the item number instead",
+            query,
+            matching.len()
+        )),
+    }
+}
+
+/// Rewrites the body with the item on `line` checked or unchecked, keeping
+/// everything else, including the indentation and list marker, as it was
+pub fn set_checked(body: &str, line: usize, checked: bool) -> String {
+    body.split('\n')
+        .enumerate()
+        .map(|(i, text)| match parse_line(text) {
+            Some((prefix, _, _)) if i == line => {
+                let mark = if checked { 'x' } else { ' ' };
+                format!("{}{}{}", prefix, mark, &text[prefix.len() + 1..])
+            }
+            _ => text.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str =
+        "Release\n- [ ] write notes\n  * [x] Tag release\n- [] not an item\n- [ ] publish";
+
+    #[test]
+    fn test_parse_checklist() {
+        let items = parse_checklist(BODY);
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[1],
+            ChecklistItem {
+                index: 2,
+                line: 2,
+                checked: true,
+                text: "Tag release".to_string(),
+            }
+        );
+        assert_eq!(progress(BODY), Some((1, 3)));
+        assert_eq!(progress("no list"), None);
+        assert!(has_unchecked(BODY));
+    }
+
+    #[test]
+    fn test_find_and_toggle() {
+        let items = parse_checklist(BODY);
+        assert_eq!(find_item(&items, "3").unwrap().text, "publish");
+        assert_eq!(find_item(&items, "NOTES").unwrap().index, 1);
+        assert!(find_item(&items, "4").is_err());
+        assert!(find_item(&items, "e").is_err());
+
+        let checked = set_checked(BODY, 1, true);
+        assert_eq!(
+            checked,
+            "Release\n- [x] write notes\n  * [x] Tag release\n- [] not an item\n- [ ] publish"
+        );
+        assert_eq!(
+            set_checked(&checked, 2, false).lines().nth(2),
+            Some("  * [ ] Tag release")
+        );
+        assert!(!has_unchecked(&set_checked(&checked, 4, true)));
+    }
+}
@@ -0,0 +1,75 @@
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+
+use super::parse_date;
+
+/// Describes the date formats `parse_relative_date` understands, for error messages
+pub const RELATIVE_DATE_HELP: &str =
+    "use YYYY-MM-DD, today, tomorrow, a weekday such as friday, or e.g. 3d, 2w or \"in 3 days\"";
+
+/// Parses a date given either as `YYYY-MM-DD` or relative to `today`.
+///
+/// Understood are `today`, `tomorrow`, `yesterday`, weekday names (`friday`,
+/// `fri`) meaning the next such day with today included, and offsets like
+/// `3d`, `2w`, `in 3 days` or `in 2 weeks`.
+///
+/// # Returns
+///
+/// `None` if the text is not a date in any of these forms.
+pub fn parse_relative_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+
+    match input.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.checked_add_days(Days::new(1)),
+        "yesterday" => return today.checked_sub_days(Days::new(1)),
+        _ => {}
+    }
+
+    if let Ok(date) = parse_date(&input) {
+        return Some(date);
+    }
+
+    if let Ok(weekday) = input.parse::<Weekday>() {
+        let ahead =
+            (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+        return today.checked_add_days(Days::new(ahead.into()));
+    }
+
+    let offset = input.strip_prefix("in ").unwrap_or(&input).replace(' ', "");
+    let split = offset.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = offset.split_at(split);
+    let count = count.parse::<u64>().ok()?;
+    let days = match unit {
+        "d" | "day" | "days" => count,
+        "w" | "week" | "weeks" => count.checked_mul(7)?,
+        _ => return None,
+    };
+
+    today.checked_add_days(Days::new(days))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_relative_date() {
+        // A Monday
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+
+        assert_eq!(parse_relative_date("today", today), Some(today));
+        assert_eq!(parse_relative_date("Tomorrow", today), date(2026, 10, 20));
+        assert_eq!(parse_relative_date("friday", today), date(2026, 10, 23));
+        assert_eq!(parse_relative_date("fri", today), date(2026, 10, 23));
+        assert_eq!(parse_relative_date("monday", today), Some(today));
+        assert_eq!(parse_relative_date("sunday", today), date(2026, 10, 25));
+        assert_eq!(parse_relative_date("3d", today), date(2026, 10, 22));
+        assert_eq!(parse_relative_date("in 2 weeks", today), date(2026, 11, 2));
+        assert_eq!(parse_relative_date("2026-12-24", today), date(2026, 12, 24));
+
+        assert_eq!(parse_relative_date("someday", today), None);
+        assert_eq!(parse_relative_date("3x", today), None);
+        assert_eq!(parse_relative_date("in days", today), None);
+    }
+}
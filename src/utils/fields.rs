@@ -0,0 +1,310 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::error::{JotError, JotResult};
+
+/// Separates key and value of an inline field such as `hours::2.5`
+pub const INLINE_FIELD_SEPARATOR: &str = "::";
+
+/// Names that can't be used as field keys because they clash with the columns
+/// every entry already has in exports and in the editor front matter
+const RESERVED_KEYS: [&str; 8] = [
+    "date", "title", "body", "tags", "kind", "status", "priority", "due",
+];
+
+/// Turns a raw key such as `Hours` into a field key.
+///
+/// Keys are lowercased, start with a letter and continue with letters, digits,
+/// `_` or `-`.
+///
+/// # Returns
+///
+/// `None` if the key is invalid or reserved.
+pub fn normalize_key(raw: &str) -> Option<String> {
+    let key = raw.trim().to_lowercase();
+    let mut chars = key.chars();
+    let valid = chars.next().is_some_and(char::is_alphabetic)
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+
+    (valid && !RESERVED_KEYS.contains(&key.as_str())).then_some(key)
+}
+
+/// Parses a `key=value` argument as given to `--field`
+///
+/// # Errors
+///
+/// Returns a message describing the problem, for the caller to wrap in its
+/// own error.
+pub fn parse_field_arg(arg: &str) -> Result<(String, String), String> {
+    let (key, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid field '{}', expected KEY=VALUE", arg))?;
+    let value = value.trim();
+    let key = normalize_key(key).ok_or_else(|| invalid_key(key))?;
+
+    if value.is_empty() {
+        return Err(format!("Field '{}' has no value", key));
+    }
+
+    Ok((key, value.to_string()))
+}
+
+/// Parses an inline `key::value` word; anything else is `None`.
+///
+/// The key has to be written as a plain lowercase identifier and the value
+/// can't contain another separator, so paths in code such as `std::fs::read`
+/// or `Vec::new` stay part of the text.
+fn parse_inline_field(word: &str) -> Option<(String, String)> {
+    let (key, value) = word.split_once(INLINE_FIELD_SEPARATOR)?;
+    if value.is_empty() || value.contains(INLINE_FIELD_SEPARATOR) {
+        return None;
+    }
+    let normalized = normalize_key(key).filter(|k| k == key)?;
+    Some((normalized, value.to_string()))
+}
+
+/// Removes the inline fields from one line, keeping everything else as it was
+/// written, including the spacing between the other words.
+///
+/// # Returns
+///
+/// The rest of the line, or `None` if it has no fields.
+fn strip_inline_fields(line: &str, fields: &mut BTreeMap<String, String>) -> Option<String> {
+    let mut kept = String::new();
+    let mut rest = line;
+    let mut skip_space = false;
+    let mut found = false;
+
+    while !rest.is_empty() {
+        let space_end = rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len());
+        let (space, after) = rest.split_at(space_end);
+        let word_end = after.find(char::is_whitespace).unwrap_or(after.len());
+        let (word, after) = after.split_at(word_end);
+        rest = after;
+
+        if !skip_space {
+            kept.push_str(space);
+        }
+        // The space after a field goes with it
+        skip_space = match parse_inline_field(word) {
+            Some((key, value)) => {
+                fields.insert(key, value);
+                found = true;
+                true
+            }
+            None => {
+                kept.push_str(word);
+                false
+            }
+        };
+    }
+    found.then(|| kept.trim_end().to_string())
+}
+
+/// Takes the inline `key::value` fields out of free text.
+///
+/// A field is a whole whitespace-separated word; text that isn't a valid field
+/// is never removed.
+///
+/// # Returns
+///
+/// The fields, later ones overriding earlier ones with the same key, and the
+/// text without them. Lines left empty by the removal are dropped.
+pub fn extract_fields(content: &str) -> (BTreeMap<String, String>, String) {
+    let mut fields = BTreeMap::new();
+    let mut lines = Vec::new();
+
+    for line in content.lines() {
+        match strip_inline_fields(line, &mut fields) {
+            None => lines.push(line.to_string()),
+            Some(kept) if !kept.trim().is_empty() => lines.push(kept),
+            Some(_) => {}
+        }
+    }
+
+    (fields, lines.join("\n").trim().to_string())
+}
+
+/// Renders fields as `key: value` pairs separated by commas
+pub fn format_fields(fields: &BTreeMap<String, String>) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| format!("{}: {}", key, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    /// Operators in the order they are tried, two-character ones first
+    const OPERATORS: [(&'static str, Comparison); 6] = [
+        (">=", Comparison::Ge),
+        ("<=", Comparison::Le),
+        ("!=", Comparison::Ne),
+        (">", Comparison::Gt),
+        ("<", Comparison::Lt),
+        ("=", Comparison::Eq),
+    ];
+
+    /// Splits a leading operator such as `>=` off the text
+    pub fn strip_operator(text: &str) -> Option<(Comparison, &str)> {
+        Self::OPERATORS
+            .iter()
+            .find_map(|(op, comparison)| text.strip_prefix(op).map(|rest| (*comparison, rest)))
+    }
+
+    /// Returns true if `ordering`, the actual value compared with the
+    /// expected one, satisfies the comparison
+    pub fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering == Ordering::Equal,
+            Comparison::Ne => ordering != Ordering::Equal,
+            Comparison::Lt => ordering == Ordering::Less,
+            Comparison::Le => ordering != Ordering::Greater,
+            Comparison::Gt => ordering == Ordering::Greater,
+            Comparison::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// A condition on a field such as `hours>2` or `project=apollo`
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldCondition {
+    pub key: String,
+    pub comparison: Comparison,
+    pub value: String,
+}
+
+impl FieldCondition {
+    /// Parses a condition term.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(None)` if the term is not a condition, i.e. has no comparison
+    /// operator directly after a valid key.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `JotError::SearchError` if the condition has no value.
+    pub fn parse(term: &str) -> JotResult<Option<Self>> {
+        let Some(index) = term.find(['<', '>', '=', '!']) else {
+            return Ok(None);
+        };
+        let (key, rest) = term.split_at(index);
+        let Some(key) = normalize_key(key) else {
+            return Ok(None);
+        };
+        let Some((comparison, value)) = Comparison::strip_operator(rest) else {
+            return Ok(None);
+        };
+
+        if value.is_empty() {
+            return Err(JotError::SearchError(format!(
+                "Missing value in condition '{}'",
+                term
+            )));
+        }
+
+        Ok(Some(Self {
+            key,
+            comparison,
+            value: value.to_string(),
+        }))
+    }
+
+    /// Returns true if the fields satisfy the condition. Values are compared as
+    /// numbers when both are numeric, otherwise as case-insensitive text.
+    /// A missing field never matches.
+    pub fn matches(&self, fields: &BTreeMap<String, String>) -> bool {
+        let Some(actual) = fields.get(&self.key) else {
+            return false;
+        };
+
+        let ordering = match (actual.parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(actual), Ok(expected)) => actual.partial_cmp(&expected),
+            _ => Some(actual.to_lowercase().cmp(&self.value.to_lowercase())),
+        };
+
+        ordering.is_some_and(|o| self.comparison.holds(o))
+    }
+}
+
+/// Describes why a field name was rejected
+pub fn invalid_key(key: &str) -> String {
+    format!(
+        "Invalid field name '{}': use letters, digits, '_' or '-', and none of {}",
+        key.trim(),
+        RESERVED_KEYS.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(
+            parse_field_arg("Hours= 2.5").unwrap(),
+            ("hours".to_string(), "2.5".to_string())
+        );
+        assert!(parse_field_arg("hours").is_err());
+        assert!(parse_field_arg("hours=").is_err());
+        assert!(parse_field_arg("title=x").is_err());
+        assert!(parse_field_arg("2x=1").is_err());
+
+        let (found, body) =
+            extract_fields("Worked on it project::apollo hours::2.5\nmood::4\nmore a::");
+        assert_eq!(
+            found,
+            fields(&[("hours", "2.5"), ("mood", "4"), ("project", "apollo")])
+        );
+        assert_eq!(body, "Worked on it\nmore a::");
+
+        // Paths in code are text, not fields
+        let (found, body) = extract_fields("call std::fs::read and Vec::new hours::1");
+        assert_eq!(found, fields(&[("hours", "1")]));
+        assert_eq!(body, "call std::fs::read and Vec::new");
+        let (found, body) = extract_fields("  - [ ] mood::3  keep  spacing\nx::y::z");
+        assert_eq!(found, fields(&[("mood", "3")]));
+        assert_eq!(body, "- [ ] keep  spacing\nx::y::z");
+    }
+
+    #[test]
+    fn test_field_conditions() {
+        let entry = fields(&[("hours", "2.5"), ("project", "Apollo")]);
+        let matches = |term: &str| {
+            FieldCondition::parse(term)
+                .unwrap()
+                .unwrap()
+                .matches(&entry)
+        };
+
+        assert!(matches("hours>2"));
+        assert!(matches("hours<=2.5"));
+        assert!(!matches("hours>10"));
+        assert!(matches("project=apollo"));
+        assert!(matches("project!=gemini"));
+        assert!(!matches("mood>=1"));
+
+        assert_eq!(FieldCondition::parse("deploy").unwrap(), None);
+        assert_eq!(FieldCondition::parse("tag:a=b").unwrap(), None);
+        assert!(FieldCondition::parse("hours>").is_err());
+    }
+}
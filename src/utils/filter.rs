@@ -0,0 +1,414 @@
+use chrono::NaiveDate;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+
+use crate::error::{JotError, JotResult};
+use crate::storage::{
+    config::JournalConfig,
+    index::{Candidates, SearchIndex},
+    Entry, Tag, TaskStatus,
+};
+
+use super::{
+    checklist, do_tags_match,
+    query::{self, Query, TextOptions},
+    TagMatch,
+};
+
+/// Selects entries by tags, date range, kind, text and a query.
+///
+/// This is the single place where `view`, `search` and the batch-editing
+/// commands decide whether an entry is part of their selection.
+pub struct EntryFilter {
+    pub tags: Vec<Tag>,
+    pub match_type: TagMatch,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Only entries changed on or after this date
+    pub modified_since: Option<NaiveDate>,
+    /// The entry has to be of one of these kinds; empty allows every kind
+    pub kinds: Vec<String>,
+    /// The entry has to be a task with one of these statuses; empty allows
+    /// every entry
+    pub statuses: Vec<TaskStatus>,
+    /// Only entries with checklist items that aren't checked yet
+    pub unchecked: bool,
+    /// Every term has to appear in the title or body
+    pub text: Vec<String>,
+    pub case_sensitive: bool,
+    pub fuzzy: bool,
+    /// A parsed query the entry has to satisfy as well
+    pub query: Option<Query>,
+    /// A regular expression that has to match part of the entry
+    pub regex: Option<RegexFilter>,
+    /// The entries text terms can match, from the search index
+    pub candidates: HashMap<String, Candidates>,
+    /// The rules the entry's tags are compared under, so tags stored before
+    /// normalisation was turned on still match
+    pub tag_cfg: Option<JournalConfig>,
+}
+
+impl Default for EntryFilter {
+    fn default() -> Self {
+        Self {
+            tags: Vec::new(),
+            match_type: TagMatch::Any,
+            from: None,
+            to: None,
+            modified_since: None,
+            kinds: Vec::new(),
+            statuses: Vec::new(),
+            unchecked: false,
+            text: Vec::new(),
+            case_sensitive: false,
+            fuzzy: false,
+            query: None,
+            regex: None,
+            candidates: HashMap::new(),
+            tag_cfg: None,
+        }
+    }
+}
+
+impl EntryFilter {
+    /// Parses a filter query such as `tag:work AND (deploy OR tag:ops) NOT tag:draft`.
+    /// See [`Query::parse`] for the syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `JotError::SearchError` describing where the query is invalid.
+    pub fn parse(query: &str, cfg: &JournalConfig) -> JotResult<Self> {
+        Ok(Self {
+            query: Query::parse(query, cfg)?,
+            tag_cfg: Some(cfg.clone()),
+            ..Self::default()
+        })
+    }
+
+    /// Looks up the entries each text term can match in the search index, so
+    /// that other entries don't have to be searched. Case-sensitive and fuzzy
+    /// terms aren't looked up.
+    pub fn use_index(&mut self, index: &SearchIndex) {
+        if self.case_sensitive || self.fuzzy {
+            return;
+        }
+
+        let terms = self
+            .text
+            .iter()
+            .map(String::as_str)
+            .chain(self.query.iter().flat_map(Query::all_text_terms));
+        let candidates = terms
+            .filter_map(|term| Some((term.to_string(), index.text_candidates(term)?)))
+            .collect();
+        self.candidates = candidates;
+    }
+
+    fn text_options(&self) -> TextOptions<'_> {
+        TextOptions {
+            case_sensitive: self.case_sensitive,
+            fuzzy: self.fuzzy,
+            candidates: Some(&self.candidates),
+            tag_cfg: self.tag_cfg.as_ref(),
+        }
+    }
+
+    /// Returns true if the entry is part of the selection
+    pub fn matches(&self, entry: &Entry) -> bool {
+        if self.from.is_some_and(|from| entry.date < from) {
+            return false;
+        }
+        if self.to.is_some_and(|to| entry.date > to) {
+            return false;
+        }
+        if self
+            .modified_since
+            .is_some_and(|since| entry.last_modified().date_naive() < since)
+        {
+            return false;
+        }
+        if !do_tags_match(
+            &self.tags,
+            &entry.tags,
+            self.match_type,
+            self.tag_cfg.as_ref(),
+        ) {
+            return false;
+        }
+        if !self.kinds.is_empty() && !self.kinds.contains(&entry.kind) {
+            return false;
+        }
+        if !self.statuses.is_empty()
+            && !entry
+                .task
+                .as_ref()
+                .is_some_and(|t| self.statuses.contains(&t.status))
+        {
+            return false;
+        }
+        if self.unchecked && !checklist::has_unchecked(&entry.body) {
+            return false;
+        }
+        if !self
+            .text
+            .iter()
+            .all(|term| query::text_matches(entry, term, self.text_options()))
+        {
+            return false;
+        }
+
+        if self.regex.as_ref().is_some_and(|r| !r.matches(entry)) {
+            return false;
+        }
+
+        self.query
+            .as_ref()
+            .is_none_or(|q| q.matches(entry, self.text_options()))
+    }
+}
+
+/// Parts of an entry a regular expression can be matched against
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum RegexScope {
+    Body,
+    Title,
+    /// Each tag name, without the `#`
+    Tags,
+}
+
+/// A compiled regular expression and the parts of an entry it applies to
+pub struct RegexFilter {
+    pub regex: Regex,
+    pub scopes: Vec<RegexScope>,
+}
+
+impl RegexFilter {
+    /// Compiles the pattern, ignoring case unless `case_sensitive` is set.
+    /// `^` and `$` match at the start and end of every line, as with grep.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `JotError::SearchError` if the pattern is not a valid regular expression.
+    pub fn new(pattern: &str, scopes: &[RegexScope], case_sensitive: bool) -> JotResult<Self> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|e| {
+                JotError::SearchError(format!("Invalid regular expression '{}': {}", pattern, e))
+            })?;
+
+        Ok(Self {
+            regex,
+            scopes: scopes.to_vec(),
+        })
+    }
+
+    /// Returns true if the expression matches any of the entry's scoped parts
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.scopes.iter().any(|scope| match scope {
+            RegexScope::Body => self.regex.is_match(&entry.body),
+            RegexScope::Title => entry
+                .title
+                .as_deref()
+                .is_some_and(|t| self.regex.is_match(t)),
+            RegexScope::Tags => entry.tags.iter().any(|t| self.regex.is_match(&t.name)),
+        })
+    }
+}
+
+/// Orders in which selected entries can be listed
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    /// By ID, the order entries were added in
+    Id,
+    /// By the date of the entry
+    Date,
+    /// By creation time
+    Created,
+    /// By last modification time
+    Modified,
+    /// By task priority, highest first; entries that aren't tasks come last
+    Priority,
+    /// By due date, soonest first; entries without one come last
+    Due,
+}
+
+/// Sorts entries oldest first (or by priority) by the given key; ties keep
+/// their previous order
+pub fn sort_entries(entries: &mut [&Entry], key: SortKey) {
+    sort_by_entry(entries, key, |e| e);
+}
+
+/// Sorts items holding entries, like search results with their scores, by a
+/// key of their entries
+pub fn sort_by_entry<T>(items: &mut [T], key: SortKey, entry: impl Fn(&T) -> &Entry) {
+    match key {
+        SortKey::Id => items.sort_by_key(|i| entry(i).id),
+        SortKey::Date => items.sort_by_key(|i| entry(i).date),
+        SortKey::Created => items.sort_by_key(|i| entry(i).timestamp),
+        SortKey::Modified => items.sort_by_key(|i| entry(i).last_modified()),
+        SortKey::Due => items.sort_by_key(|i| {
+            let due = entry(i).task.as_ref().and_then(|t| t.due);
+            (due.is_none(), due)
+        }),
+        SortKey::Priority => {
+            items.sort_by_key(|i| std::cmp::Reverse(entry(i).task.as_ref().map(|t| t.priority)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::parse_date;
+
+    fn entry(body: &str, tags: &[&str], date: &str) -> Entry {
+        let mut entry = Entry::new(
+            0,
+            body.to_string(),
+            tags.iter().map(|t| Tag::new(t.to_string())).collect(),
+        );
+        entry.date = parse_date(date).unwrap();
+        entry
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let cfg = JournalConfig::default();
+        let filter = EntryFilter::parse("tag:work #x from:2024-01-01 Deploy", &cfg).unwrap();
+        assert_eq!(
+            filter.query.unwrap().text_terms(),
+            vec!["#x".to_string(), "Deploy".to_string()]
+        );
+        assert!(EntryFilter::parse("", &cfg).unwrap().query.is_none());
+
+        assert!(EntryFilter::parse("tag:", &cfg).is_err());
+        assert!(EntryFilter::parse("tag:#", &cfg).is_err());
+        assert!(EntryFilter::parse("from:yesterday", &cfg).is_err());
+        assert!(EntryFilter::parse("colour:red", &cfg).is_err());
+        assert!(EntryFilter::parse("http://example.com", &cfg).is_ok());
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let mut a = entry("a", &[], "2024-01-02");
+        let mut b = entry("b", &[], "2024-01-01");
+        a.id = 0;
+        b.id = 1;
+        // `a` was created after `b` but `b` was modified last
+        b.timestamp = a.timestamp - chrono::Duration::days(1);
+        a.modified_at = Some(a.timestamp);
+        b.modified_at = Some(a.timestamp + chrono::Duration::days(1));
+
+        let order = |key| {
+            let mut entries = vec![&a, &b];
+            sort_entries(&mut entries, key);
+            entries.iter().map(|e| e.body.as_str()).collect::<String>()
+        };
+        assert_eq!(order(SortKey::Id), "ab");
+        assert_eq!(order(SortKey::Date), "ba");
+        assert_eq!(order(SortKey::Created), "ba");
+        assert_eq!(order(SortKey::Modified), "ab");
+    }
+
+    #[test]
+    fn test_matches() {
+        let cfg = JournalConfig::default();
+        let e = entry("Deployed the API", &["work", "ops"], "2024-02-10");
+
+        assert!(EntryFilter::default().matches(&e));
+        assert!(EntryFilter::parse("tag:work tag:ops deployed", &cfg)
+            .unwrap()
+            .matches(&e));
+        assert!(!EntryFilter::parse("tag:work tag:home", &cfg)
+            .unwrap()
+            .matches(&e));
+        assert!(EntryFilter::parse("from:2024-02-10 to:2024-02-10", &cfg)
+            .unwrap()
+            .matches(&e));
+        assert!(!EntryFilter::parse("to:2024-02-09", &cfg)
+            .unwrap()
+            .matches(&e));
+        assert!(!EntryFilter::parse("api database", &cfg)
+            .unwrap()
+            .matches(&e));
+
+        let mut modified = e.clone();
+        modified.modified_at = parse_date("2024-03-01")
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .map(|t| t.and_utc());
+        assert!(EntryFilter::parse("modified:2024-03-01", &cfg)
+            .unwrap()
+            .matches(&modified));
+        assert!(!EntryFilter::parse("modified:2024-03-02", &cfg)
+            .unwrap()
+            .matches(&modified));
+
+        let mut measured = e.clone();
+        measured
+            .fields
+            .insert("hours".to_string(), "2.5".to_string());
+        let hours = EntryFilter::parse("hours>2 deploy", &cfg).unwrap();
+        assert!(hours.matches(&measured));
+        assert!(!hours.matches(&e));
+
+        let mut task = e.clone();
+        task.kind = "task".to_string();
+        let tasks = EntryFilter::parse("kind:task OR kind:Log", &cfg).unwrap();
+        assert!(tasks.matches(&task));
+        assert!(!tasks.matches(&e));
+
+        task.sync_task();
+        let open = EntryFilter::parse("status:in-progress", &cfg).unwrap();
+        assert!(!open.matches(&task));
+        task.task.as_mut().unwrap().status = TaskStatus::InProgress;
+        assert!(open.matches(&task));
+        assert!(!open.matches(&e));
+        assert!(EntryFilter::parse("status:later", &cfg).is_err());
+
+        let mut list = e.clone();
+        list.body = "- [x] done\n- [ ] open".to_string();
+        let unchecked = EntryFilter::parse("has:unchecked", &cfg).unwrap();
+        assert!(unchecked.matches(&list));
+        assert!(!unchecked.matches(&e));
+        assert!(EntryFilter::parse("has:nothing", &cfg).is_err());
+
+        let mut titled = e.clone();
+        titled.title = Some("Release notes".to_string());
+        assert!(EntryFilter::parse("release api", &cfg)
+            .unwrap()
+            .matches(&titled));
+
+        let case_sensitive = EntryFilter {
+            text: vec!["api".to_string()],
+            case_sensitive: true,
+            ..EntryFilter::default()
+        };
+        assert!(!case_sensitive.matches(&e));
+    }
+
+    #[test]
+    fn test_regex_filter() {
+        let mut e = entry("Deployed v2.1 to prod", &["ops/deploys"], "2024-02-10");
+        let regex = |pattern, scopes: &[RegexScope], case_sensitive| EntryFilter {
+            regex: Some(RegexFilter::new(pattern, scopes, case_sensitive).unwrap()),
+            ..EntryFilter::default()
+        };
+
+        assert!(regex(r"v\d+\.\d+", &[RegexScope::Body], false).matches(&e));
+        assert!(regex("^deployed", &[RegexScope::Body], false).matches(&e));
+        assert!(!regex("^deployed", &[RegexScope::Body], true).matches(&e));
+        assert!(!regex("^ops/", &[RegexScope::Body], false).matches(&e));
+        assert!(regex("^ops/", &[RegexScope::Body, RegexScope::Tags], false).matches(&e));
+
+        e.title = Some("Release".to_string());
+        assert!(regex("release", &[RegexScope::Title], false).matches(&e));
+
+        let error = RegexFilter::new("(unclosed", &[RegexScope::Body], false)
+            .err()
+            .unwrap();
+        assert!(matches!(error, JotError::SearchError(_)));
+    }
+}
@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+/// Score for each matched character
+const MATCH: i32 = 16;
+/// Penalty for the first character skipped between two matched ones
+const GAP_START: i32 = -3;
+/// Penalty for every further skipped character
+const GAP_EXTEND: i32 = -1;
+/// Bonus for matching the character right after the previous match
+const CONSECUTIVE: i32 = 4;
+/// Bonus for matching the first character of a word
+const WORD_START: i32 = 8;
+/// Bonus for matching an uppercase letter after a lowercase one, as in `camelCase`
+const CAMEL_CASE: i32 = 7;
+
+/// Where a pattern was found in a text and how well it matched
+#[derive(Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Byte offsets of the matched characters in the text
+    pub positions: Vec<usize>,
+}
+
+impl FuzzyMatch {
+    /// Byte ranges of the runs of matched characters, for highlighting
+    pub fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for &start in &self.positions {
+            let end = start + text[start..].chars().next().map_or(0, char::len_utf8);
+            match ranges.last_mut() {
+                Some(last) if last.end == start => last.end = end,
+                _ => ranges.push(start..end),
+            }
+        }
+        ranges
+    }
+}
+
+/// Bonus for matching the character at `index` based on what comes before it
+fn position_bonus(chars: &[char], index: usize) -> i32 {
+    let Some(previous) = index.checked_sub(1).map(|i| chars[i]) else {
+        return WORD_START;
+    };
+    let current = chars[index];
+    if !previous.is_alphanumeric() && current.is_alphanumeric() {
+        WORD_START
+    } else if previous.is_lowercase() && current.is_uppercase() {
+        CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+/// Finds the characters of `pattern` in order in `text`, not necessarily next
+/// to each other, and scores the best way to do so.
+///
+/// Matches on word starts and runs of consecutive characters score higher,
+/// and characters skipped between matches cost a little, so `fm` ranks
+/// "fuzzy matcher" above "format". Characters before the first match are free.
+///
+/// # Returns
+///
+/// `None` if the text doesn't contain the pattern's characters in order.
+pub fn fuzzy_match(text: &str, pattern: &str, case_sensitive: bool) -> Option<FuzzyMatch> {
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let (offsets, chars): (Vec<usize>, Vec<char>) = text.char_indices().unzip();
+    let folded = chars.iter().map(|c| fold(*c)).collect::<Vec<_>>();
+    let needle = pattern.chars().map(fold).collect::<Vec<_>>();
+
+    if needle.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    // Cheap check before scoring: every character has to appear in order
+    let mut rest = folded.iter();
+    if !needle.iter().all(|n| rest.any(|c| c == n)) {
+        return None;
+    }
+
+    let width = chars.len();
+    // scores[i][j]: best score with the i-th pattern character matched at j;
+    // from[i][j]: where the previous pattern character was matched then
+    let mut scores = vec![vec![None::<i32>; width]; needle.len()];
+    let mut from = vec![vec![0usize; width]; needle.len()];
+
+    for (j, c) in folded.iter().enumerate() {
+        if *c == needle[0] {
+            scores[0][j] = Some(MATCH + 2 * position_bonus(&chars, j));
+        }
+    }
+
+    for i in 1..needle.len() {
+        // Best score of a previous match at least one character back, with the
+        // gap penalty up to the current column
+        let mut gapped: Option<(i32, usize)> = None;
+        for j in 1..width {
+            if j >= 2 {
+                let extended = gapped.map(|(score, k)| (score + GAP_EXTEND, k));
+                let opened = scores[i - 1][j - 2].map(|score| (score + GAP_START, j - 2));
+                gapped = extended.into_iter().chain(opened).max_by_key(|(score, _)| *score);
+            }
+            if folded[j] != needle[i] {
+                continue;
+            }
+
+            let adjacent = scores[i - 1][j - 1].map(|score| (score + CONSECUTIVE, j - 1));
+            if let Some((score, k)) = adjacent.into_iter().chain(gapped).max_by_key(|(score, _)| *score) {
+                scores[i][j] = Some(score + MATCH + position_bonus(&chars, j));
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let last = needle.len() - 1;
+    let (mut j, score) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|s| (j, s)))
+        .max_by_key(|(j, score)| (*score, std::cmp::Reverse(*j)))?;
+
+    let mut positions = vec![offsets[j]];
+    for i in (1..=last).rev() {
+        j = from[i][j];
+        positions.push(offsets[j]);
+    }
+    positions.reverse();
+
+    Some(FuzzyMatch { score, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(text: &str, pattern: &str) -> i32 {
+        fuzzy_match(text, pattern, false).unwrap().score
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(fuzzy_match("fuzzy matcher", "fm", false).unwrap().positions, vec![0, 6]);
+        assert!(fuzzy_match("abc", "acb", false).is_none());
+        assert!(fuzzy_match("abc", "ad", false).is_none());
+        assert!(fuzzy_match("ab", "abc", false).is_none());
+        assert!(fuzzy_match("Deploy", "dep", true).is_none());
+        assert_eq!(fuzzy_match("text", "", false).unwrap().score, 0);
+
+        // Word starts and consecutive characters beat scattered ones
+        assert!(score("data pipeline", "dp") > score("deploy", "dp"));
+        assert!(score("deploy", "dep") > score("dxexp", "dep"));
+        assert!(score("fuzzy matcher", "fm") > score("format", "fm"));
+        assert!(score("myFile", "f") > score("myfile", "f"));
+
+        // The best alignment is chosen, not the first one found
+        let found = fuzzy_match("axxxb ab", "ab", false).unwrap();
+        assert_eq!(found.positions, vec![6, 7]);
+        assert_eq!(found.ranges("axxxb ab"), vec![6..8]);
+
+        let found = fuzzy_match("Café au lait", "éal", false).unwrap();
+        assert_eq!(found.ranges("Café au lait"), vec![3..5, 6..7, 9..10]);
+    }
+}
@@ -0,0 +1,232 @@
+use colored::Colorize;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::text;
+
+/// Byte ranges of every occurrence of `term` in `text`.
+///
+/// Case is folded one character at a time rather than on the whole string, so
+/// the ranges always line up with the original text even when lowercasing
+/// changes a character's length, as with `İ`.
+pub fn find_term(text: &str, term: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+    let fold = |c: char| -> Vec<char> {
+        if case_sensitive {
+            vec![c]
+        } else {
+            c.to_lowercase().collect()
+        }
+    };
+    let needle = term.chars().flat_map(fold).collect::<Vec<_>>();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    // Each folded character with the byte range of the character it came from
+    let folded = text
+        .char_indices()
+        .flat_map(|(i, c)| {
+            let span = i..i + c.len_utf8();
+            fold(c).into_iter().map(move |f| (f, span.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start + needle.len() <= folded.len() {
+        let window = &folded[start..start + needle.len()];
+        if window.iter().map(|(c, _)| c).eq(needle.iter()) {
+            ranges.push(window[0].1.start..window[needle.len() - 1].1.end);
+            start += needle.len();
+        } else {
+            start += 1;
+        }
+    }
+    ranges
+}
+
+/// Byte ranges of the runs of words in `text` with the same stems as the
+/// words of `term`, so a search for `deploying` also marks "deployed" and one
+/// for `deploying the api` marks "Deployed the API" but no other "the"
+pub fn find_stemmed(text: &str, term: &str) -> Vec<Range<usize>> {
+    let stems = text::stems(term);
+    if stems.is_empty() {
+        return Vec::new();
+    }
+
+    // Words split the same way `text::tokenize` does, with their byte ranges
+    let mut words: Vec<(Range<usize>, String)> = Vec::new();
+    let mut start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push((s..i, text::stem(&text[s..i].to_lowercase())));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    words
+        .windows(stems.len())
+        .filter(|window| window.iter().map(|(_, stem)| stem).eq(stems.iter()))
+        .map(|window| window[0].0.start..window[stems.len() - 1].0.end)
+        .collect()
+}
+
+/// Widens the ranges to whole grapheme clusters and merges overlapping ones,
+/// so a highlight never splits an accent from its letter
+fn to_graphemes(text: &str, ranges: &[Range<usize>]) -> Vec<Range<usize>> {
+    let boundaries = text
+        .grapheme_indices(true)
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect::<Vec<_>>();
+    let floor = |offset: usize| boundaries[boundaries.partition_point(|&b| b <= offset) - 1];
+    let ceil = |offset: usize| boundaries[boundaries.partition_point(|&b| b < offset)];
+
+    let mut snapped = ranges
+        .iter()
+        .filter(|range| !range.is_empty() && range.end <= text.len())
+        .map(|range| floor(range.start)..ceil(range.end))
+        .collect::<Vec<_>>();
+    snapped.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in snapped {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Highlights the given byte ranges of the text, or returns it unchanged
+/// when `color` is false
+pub fn highlight(text: &str, ranges: &[Range<usize>], color: bool) -> String {
+    if !color {
+        return text.to_string();
+    }
+
+    let mut highlighted = String::new();
+    let mut end = 0;
+    for range in to_graphemes(text, ranges) {
+        highlighted.push_str(&text[end..range.start]);
+        highlighted.push_str(&text[range.clone()].on_green().to_string());
+        end = range.end;
+    }
+    highlighted.push_str(&text[end..]);
+    highlighted
+}
+
+/// Splits the text into lines with the ranges highlighted, keeping only lines
+/// within `context` lines of one containing a highlight, like `grep -C`.
+/// Every line is kept when `context` is `None`, and only the first lines when
+/// nothing is highlighted. Highlights are only added when `color` is true.
+///
+/// # Returns
+///
+/// The kept lines with their line numbers, counting from zero.
+pub fn excerpt(
+    text: &str,
+    ranges: &[Range<usize>],
+    context: Option<usize>,
+    color: bool,
+) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for line in text.split('\n') {
+        let span = start..start + line.len();
+        let hits = ranges
+            .iter()
+            .filter(|range| range.start < span.end && range.end > span.start)
+            .map(|range| {
+                range.start.max(span.start) - span.start..range.end.min(span.end) - span.start
+            })
+            .collect::<Vec<_>>();
+        lines.push((line, hits));
+        start = span.end + 1;
+    }
+
+    let hit_lines = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, hits))| !hits.is_empty())
+        .map(|(n, _)| n)
+        .collect::<Vec<_>>();
+    let keep = |n: usize| match context {
+        None => true,
+        Some(context) if hit_lines.is_empty() => n <= context,
+        Some(context) => hit_lines.iter().any(|&hit| n.abs_diff(hit) <= context),
+    };
+
+    lines
+        .into_iter()
+        .enumerate()
+        .filter(|(n, _)| keep(*n))
+        .map(|(n, (line, hits))| (n, highlight(line, &hits, color)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_term() {
+        assert_eq!(
+            find_term("Deploy, then deploy", "deploy", false),
+            vec![0..6, 13..19]
+        );
+        assert_eq!(
+            find_term("Deploy, then deploy", "deploy", true),
+            vec![13..19]
+        );
+        assert!(find_term("text", "", false).is_empty());
+
+        // Lowercasing `İ` gives two characters, which must not shift later offsets
+        let text = "İstanbul café";
+        assert_eq!(find_term(text, "CAFÉ", false), vec![10..15]);
+        assert_eq!(&text[10..15], "café");
+
+        assert_eq!(find_stemmed("We deployed it", "deploying"), vec![3..11]);
+        // A phrase marks only where its words appear together
+        assert_eq!(
+            find_stemmed("Deployed the API, then the end", "deploying the api"),
+            vec![0..16]
+        );
+        assert!(find_stemmed("text", "!!").is_empty());
+    }
+
+    #[test]
+    fn test_to_graphemes() {
+        // "e" followed by a combining acute accent is one grapheme, and empty
+        // ranges are dropped
+        let text = "cafe\u{301} bar";
+        assert_eq!(to_graphemes(text, &[3..4, 8..8]), vec![3..6]);
+        assert_eq!(to_graphemes(text, &[0..2, 1..3, 7..10]), vec![0..3, 7..10]);
+    }
+
+    #[test]
+    fn test_excerpt() {
+        let text = "one\ntwo\nthree hit\nfour\nfive\nsix\nseven hit";
+        let ranges = find_term(text, "hit", false);
+
+        let lines = |context| {
+            excerpt(text, &ranges, context, false)
+                .into_iter()
+                .map(|(n, _)| n)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lines(None), (0..7).collect::<Vec<_>>());
+        assert_eq!(lines(Some(0)), vec![2, 6]);
+        assert_eq!(lines(Some(1)), vec![1, 2, 3, 5, 6]);
+        assert_eq!(excerpt("a\nb\nc", &[], Some(1), false).len(), 2);
+        assert_eq!(excerpt(text, &ranges, Some(0), false)[0].1, "three hit");
+    }
+}
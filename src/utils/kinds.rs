@@ -0,0 +1,62 @@
+use crate::storage::{
+    config::{Config, KindConfig},
+    Entry,
+};
+
+use super::fields;
+
+/// Looks up a kind by name.
+///
+/// # Returns
+///
+/// The normalised name with its settings, or a message listing the available
+/// kinds for the caller to wrap in its own error.
+pub fn resolve_kind(name: &str, config: &Config) -> Result<(String, KindConfig), String> {
+    let name = name.trim().to_lowercase();
+    config
+        .kind(&name)
+        .map(|kind| (name.clone(), kind))
+        .ok_or_else(|| {
+            format!(
+                "Unknown kind '{}' (expected one of: {})",
+                name,
+                config
+                    .all_kinds()
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+/// The fields the entry's kind requires but the entry doesn't have
+pub fn missing_fields(entry: &Entry, kind: &KindConfig) -> Vec<String> {
+    kind.required_fields
+        .iter()
+        .filter_map(|f| fields::normalize_key(f))
+        .filter(|f| !entry.fields.contains_key(f))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_kind() {
+        let config = Config::default();
+        let (name, bookmark) = resolve_kind(" Bookmark", &config).unwrap();
+        assert_eq!(name, "bookmark");
+
+        let mut entry = Entry::new(0, "a link".to_string(), vec![]);
+        assert_eq!(missing_fields(&entry, &bookmark), vec!["url".to_string()]);
+        entry
+            .fields
+            .insert("url".to_string(), "https://example.com".to_string());
+        assert!(missing_fields(&entry, &bookmark).is_empty());
+
+        let error = resolve_kind("meeting", &config).unwrap_err();
+        assert!(error.contains("bookmark, log, note, task"));
+    }
+}
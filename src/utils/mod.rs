@@ -1,66 +1,68 @@
-use std::io::{self, Write};
+pub mod checklist;
+pub mod dates;
+pub mod fields;
+pub mod filter;
+pub mod fuzzy;
+pub mod highlight;
+pub mod kinds;
+pub mod query;
+pub mod rank;
+pub mod similarity;
+pub mod tags;
+#[cfg(test)]
+pub mod testing;
+pub mod text;
 
 use colored::Colorize;
 
-use crate::storage::{config::JournalConfig, Entry, Journal, Tag};
-
-/// Prompts the user for input and returns the trimmed input as a String.
-///
-/// # Arguments
-///
-/// * `prompt` - A string slice that holds the prompt message to display to the user.
-///
-/// # Returns
-///
-/// A `String` containing the user's input.
-pub fn get_input(prompt: &str) -> String {
-    print!("{}", prompt);
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    input.trim().to_string()
-}
+use crate::storage::{
+    config::{Config, JournalConfig},
+    Entry, Journal, Priority, Tag, TaskStatus, DEFAULT_KIND,
+};
 
+#[derive(Clone, Copy)]
 pub enum TagMatch {
     Any, // OR operation
     All, // AND operation
 }
 
 /// Checks if the tags in the query match the tags in the entry based on the match type.
+/// A query tag also matches every tag nested below it, so `work` matches `work/clientA`.
 ///
 /// # Arguments
 ///
 /// * `query_tags` - A slice of `Tag` representing the tags to query.
 /// * `entry_tags` - A slice of `Tag` representing the tags in the entry.
 /// * `match_type` - A `TagMatch` enum indicating whether to match any or all tags.
+/// * `cfg` - The tag normalisation rules to compare the entry's tags under, if any.
 ///
 /// # Returns
 ///
 /// A boolean indicating whether the tags match.
-pub fn do_tags_match(query_tags: &[Tag], entry_tags: &[Tag], match_type: TagMatch) -> bool {
+pub fn do_tags_match(
+    query_tags: &[Tag],
+    entry_tags: &[Tag],
+    match_type: TagMatch,
+    cfg: Option<&JournalConfig>,
+) -> bool {
     if query_tags.is_empty() {
         return true;
     }
 
     match match_type {
-        TagMatch::Any => query_tags.iter().any(|tag| entry_tags.contains(tag)),
-        TagMatch::All => query_tags.iter().all(|tag| entry_tags.contains(tag)),
+        TagMatch::Any => query_tags.iter().any(|tag| has_tag(entry_tags, tag, cfg)),
+        TagMatch::All => query_tags.iter().all(|tag| has_tag(entry_tags, tag, cfg)),
     }
 }
 
-/// Parses a string of tags separated by whitespace into a vector of `Tag` structs.
-///
-/// # Arguments
-///
-/// * `tags` - A string slice containing the tags separated by whitespace.
-///
-/// # Returns
-///
-/// A vector of `Tag` structs.
-pub fn parse_tags(tags: &str) -> Vec<Tag> {
-    tags.split_whitespace()
-        .map(|t| t.parse::<Tag>().unwrap())
-        .collect()
+/// Returns true if any of the tags is `query` or nested below it. With a
+/// config, the tags are compared under its normalisation rules, so a tag
+/// stored as `Work` is found by `work` once tags are lowercased.
+pub fn has_tag(tags: &[Tag], query: &Tag, cfg: Option<&JournalConfig>) -> bool {
+    tags.iter().any(|t| match cfg {
+        Some(cfg) => tags::renormalize(t, cfg).is_within(query),
+        None => t.is_within(query),
+    })
 }
 
 /// Parses a date string in the format "YYYY-MM-DD" into a `NaiveDate` struct.
@@ -71,9 +73,25 @@ pub fn parse_tags(tags: &str) -> Vec<Tag> {
 ///
 /// # Returns
 ///
-/// A `NaiveDate` struct representing the parsed date.
-pub fn parse_date(date: &str) -> chrono::NaiveDate {
-    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+/// A `NaiveDate` struct representing the parsed date, or the parse error if the
+/// string is not a valid date.
+pub fn parse_date(date: &str) -> Result<chrono::NaiveDate, chrono::ParseError> {
+    chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+}
+
+/// The count with "entry" or "entries", e.g. `1 entry`
+pub fn count_entries(count: usize) -> String {
+    format!("{} {}", count, if count == 1 { "entry" } else { "entries" })
+}
+
+/// Quotes a command-line argument for display if it is empty or contains
+/// whitespace
+pub fn quote_arg(arg: &str) -> String {
+    if arg.is_empty() || arg.contains(char::is_whitespace) {
+        format!("{:?}", arg)
+    } else {
+        arg.to_string()
+    }
 }
 
 /// Formats a journal entry into a string for display.
@@ -81,12 +99,52 @@ pub fn parse_date(date: &str) -> chrono::NaiveDate {
 /// # Arguments
 ///
 /// * `entry` - A reference to the `Entry` struct to format.
-/// * `cfg` - A `JournalConfig` struct containing configuration settings.
+/// * `config` - The configuration, for display settings and kind styles.
 ///
 /// # Returns
 ///
 /// A `String` containing the formatted entry.
-pub fn format_entry(entry: &Entry, cfg: JournalConfig) -> String {
+pub fn format_entry(entry: &Entry, config: &Config) -> String {
+    let mut formatted = format_header(entry, config);
+
+    if !entry.fields.is_empty() {
+        formatted.push_str(&format!(
+            "\n{}",
+            fields::format_fields(&entry.fields).cyan()
+        ));
+    }
+
+    if let Some(title) = &entry.title {
+        formatted.push_str(&format!("\n{}", title.bold()));
+    }
+
+    let body_colored = entry
+        .body
+        .lines()
+        .map(|line| {
+            line.split(' ')
+                .map(|word| {
+                    if word.starts_with('#') {
+                        word.bright_green().to_string()
+                    } else {
+                        word.to_string()
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect::<Vec<String>>();
+
+    formatted.push_str(&format!("\n{}\n", body_colored.join("\n")));
+    formatted.push_str(&"-".repeat(40));
+
+    formatted
+}
+
+/// The first line of a formatted entry: its ID, date, kind, task state,
+/// checklist progress and tags
+pub fn format_header(entry: &Entry, config: &Config) -> String {
+    let cfg = &config.journal_cfg;
     let mut formatted = String::new();
     formatted.push_str(
         &format!(
@@ -109,6 +167,44 @@ pub fn format_entry(entry: &Entry, cfg: JournalConfig) -> String {
         ));
     }
 
+    if let Some(label) = kind_label(entry, config) {
+        formatted.push_str(&format!(" {}", label));
+    }
+
+    if let Some(task) = &entry.task {
+        let status = match task.status {
+            TaskStatus::Open => "[ ]".normal(),
+            TaskStatus::InProgress => "[~]".yellow(),
+            TaskStatus::Done => "[x]".green(),
+            TaskStatus::Cancelled => "[-]".dimmed(),
+        };
+        formatted.push_str(&format!(" {}", status));
+
+        match task.priority {
+            Priority::High => formatted.push_str(&format!(" {}", "!high".red())),
+            Priority::Low => formatted.push_str(&format!(" {}", "!low".dimmed())),
+            Priority::Medium => {}
+        }
+
+        if let Some(due) = task.due {
+            let label = format!("due {}", due);
+            if entry.is_overdue(chrono::Local::now().date_naive()) {
+                formatted.push_str(&format!(" {}", label.red().bold()));
+            } else {
+                formatted.push_str(&format!(" {}", label.cyan()));
+            }
+        }
+    }
+
+    if let Some((done, total)) = checklist::progress(&entry.body) {
+        let label = format!("({}/{})", done, total);
+        if done == total {
+            formatted.push_str(&format!(" {}", label.green()));
+        } else {
+            formatted.push_str(&format!(" {}", label.yellow()));
+        }
+    }
+
     if !entry.tags.is_empty() {
         formatted.push_str(&format!(
             " {}",
@@ -122,55 +218,23 @@ pub fn format_entry(entry: &Entry, cfg: JournalConfig) -> String {
         ));
     }
 
-    let body_colored = entry
-        .body
-        .split_whitespace()
-        .map(|word| {
-            if word.starts_with('#') {
-                word.bright_green().to_string()
-            } else {
-                word.to_string()
-            }
-        })
-        .collect::<Vec<String>>();
-
-    formatted.push_str(&format!("\n{}\n", body_colored.join(" ")));
-    formatted.push_str(&"-".repeat(40));
-
     formatted
 }
 
-/// Performs a fuzzy match of the needle string within the haystack string.
-///
-/// # Arguments
-///
-/// * `haystack` - A string slice representing the text to search within.
-/// * `needle` - A string slice representing the text to search for.
-///
-/// # Returns
-///
-/// A boolean indicating whether the needle was found in the haystack.
-pub fn fuzzy_match(haystack: &str, needle: &str) -> bool {
-    let needle_chars = needle.chars();
-    let mut haystack_chars = haystack.chars();
-
-    for needle_char in needle_chars {
-        if let Some(haystack_char) = haystack_chars.next() {
-            if needle_char == haystack_char {
-                continue;
-            } else {
-                for next_haystack_char in haystack_chars.by_ref() {
-                    if needle_char == next_haystack_char {
-                        break;
-                    }
-                }
-            }
-        } else {
-            return false;
-        }
+/// The styled label of the entry's kind, or `None` for plain notes
+fn kind_label(entry: &Entry, config: &Config) -> Option<String> {
+    if entry.kind == DEFAULT_KIND {
+        return None;
     }
 
-    true
+    let kind = config.kind(&entry.kind).unwrap_or_default();
+    let label = kind.symbol.as_deref().unwrap_or(&entry.kind);
+    Some(
+        match kind.color.and_then(|c| c.parse::<colored::Color>().ok()) {
+            Some(color) => label.color(color).bold().to_string(),
+            None => label.bold().to_string(),
+        },
+    )
 }
 
 /// Views a journal entry by its ID.
@@ -195,7 +259,38 @@ pub fn view_by_id(journal: &Journal, id: usize) {
 pub fn print_single_entry(entry: &Entry) {
     println!("\n{}", "=".repeat(50));
     println!("Entry #{}", entry.id);
+    println!("Title: {}", entry.display_title().bold());
+    println!("Kind: {}", entry.kind);
+    if let Some(task) = &entry.task {
+        println!("Status: {}", task.status);
+        println!("Priority: {}", task.priority);
+        if let Some(due) = task.due {
+            println!("Due: {}", due);
+        }
+        if let Some(completed) = task.completed_at {
+            println!(
+                "Completed: {}",
+                completed
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+            );
+        }
+    }
     println!("Date: {}", entry.date);
+    println!(
+        "Created: {}",
+        entry
+            .timestamp
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M")
+    );
+    println!(
+        "Modified: {}",
+        entry
+            .last_modified()
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M")
+    );
     println!("\n{}\n", entry.body);
 
     let tags = entry
@@ -208,5 +303,8 @@ pub fn print_single_entry(entry: &Entry) {
     if !tags.is_empty() {
         println!("Tags: {}", tags);
     }
+    if !entry.fields.is_empty() {
+        println!("Fields: {}", fields::format_fields(&entry.fields));
+    }
     println!("{}", "=".repeat(50));
 }
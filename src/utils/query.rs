@@ -0,0 +1,583 @@
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+use crate::error::{JotError, JotResult};
+use crate::storage::{config::JournalConfig, index::Candidates, Entry, Tag, TaskStatus};
+
+use super::{
+    checklist,
+    fields::{Comparison, FieldCondition},
+    fuzzy, parse_date,
+    tags::normalize_tag,
+    text,
+};
+
+/// Field names a query term can start with, for error messages
+const FIELDS: &str = "tag, text, date, from, to, modified, due, kind, status or has";
+
+/// A single condition of a query
+#[derive(Clone, Debug, PartialEq)]
+pub enum Term {
+    /// The entry has the tag
+    Tag(Tag),
+    /// The text appears in the title or body
+    Text(String),
+    /// Compares the date of the entry
+    Date(Comparison, NaiveDate),
+    /// Compares the date the entry was last changed
+    Modified(Comparison, NaiveDate),
+    /// Compares the due date; entries without one never match
+    Due(Comparison, NaiveDate),
+    Kind(String),
+    /// The entry is a task with this status
+    Status(TaskStatus),
+    /// The entry has checklist items that aren't checked yet
+    Unchecked,
+    Field(FieldCondition),
+}
+
+/// A boolean combination of terms, e.g.
+/// `tag:work AND (text:"deploy" OR tag:ops) NOT tag:draft`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    Term(Term),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+/// How text terms are compared with the title and body
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TextOptions<'a> {
+    pub case_sensitive: bool,
+    pub fuzzy: bool,
+    /// The entries each term can match, looked up in the search index
+    pub candidates: Option<&'a HashMap<String, Candidates>>,
+    /// The rules `tag:` terms compare the entry's tags under
+    pub tag_cfg: Option<&'a JournalConfig>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    /// A term; `quoted` if it started with a quote and is plain text
+    Word { text: String, quoted: bool },
+}
+
+fn syntax_error(message: impl std::fmt::Display, position: usize) -> JotError {
+    JotError::SearchError(format!("{} at position {}", message, position))
+}
+
+/// Splits a query into tokens paired with their position, counted in characters from 1
+fn tokenize(query: &str) -> JotResult<Vec<(Token, usize)>> {
+    let chars = query.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let position = i + 1;
+        match chars[i] {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push((Token::Open, position));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((Token::Close, position));
+                i += 1;
+            }
+            first => {
+                let mut text = String::new();
+                let mut has_quotes = false;
+                while i < chars.len() && !chars[i].is_whitespace() && !"()".contains(chars[i]) {
+                    if chars[i] != '"' {
+                        text.push(chars[i]);
+                        i += 1;
+                        continue;
+                    }
+
+                    has_quotes = true;
+                    let quote = i + 1;
+                    i += 1;
+                    while i < chars.len() && chars[i] != '"' {
+                        text.push(chars[i]);
+                        i += 1;
+                    }
+                    if i == chars.len() {
+                        return Err(syntax_error("Unterminated quote", quote));
+                    }
+                    i += 1;
+                }
+
+                let token = match text.as_str() {
+                    "AND" if !has_quotes => Token::And,
+                    "OR" if !has_quotes => Token::Or,
+                    "NOT" if !has_quotes => Token::Not,
+                    _ => Token::Word {
+                        text,
+                        quoted: first == '"',
+                    },
+                };
+                tokens.push((token, position));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parses a date term value such as `>=2024-01-01`, using `default` without an operator
+fn parse_date_value(
+    value: &str,
+    default: Comparison,
+    position: usize,
+) -> JotResult<(Comparison, NaiveDate)> {
+    let (comparison, date) = Comparison::strip_operator(value).unwrap_or((default, value));
+    let date = parse_date(date)
+        .map_err(|e| syntax_error(format!("Invalid date '{}' ({})", date, e), position))?;
+    Ok((comparison, date))
+}
+
+fn parse_term(text: &str, quoted: bool, position: usize, cfg: &JournalConfig) -> JotResult<Term> {
+    if quoted {
+        return Ok(Term::Text(text.to_string()));
+    }
+
+    if let Some(condition) = FieldCondition::parse(text).map_err(|e| match e {
+        JotError::SearchError(message) => syntax_error(message, position),
+        other => other,
+    })? {
+        return Ok(Term::Field(condition));
+    }
+
+    let Some((field, value)) = text.split_once(':') else {
+        return Ok(Term::Text(text.to_string()));
+    };
+    let is_field = field.chars().all(|c| c.is_ascii_alphabetic()) && !value.starts_with("//");
+    if is_field && value.is_empty() {
+        return Err(syntax_error(
+            format!("Missing value in '{}'", text),
+            position,
+        ));
+    }
+
+    let term = match field {
+        "tag" => Term::Tag(
+            normalize_tag(value, cfg)
+                .ok_or_else(|| syntax_error(format!("Invalid tag '{}'", value), position))?,
+        ),
+        "text" => Term::Text(value.to_string()),
+        "date" => {
+            let (comparison, date) = parse_date_value(value, Comparison::Eq, position)?;
+            Term::Date(comparison, date)
+        }
+        "from" => Term::Date(Comparison::Ge, parse_date_value(value, Comparison::Eq, position)?.1),
+        "to" => Term::Date(Comparison::Le, parse_date_value(value, Comparison::Eq, position)?.1),
+        "modified" => {
+            let (comparison, date) = parse_date_value(value, Comparison::Ge, position)?;
+            Term::Modified(comparison, date)
+        }
+        "due" => {
+            let (comparison, date) = parse_date_value(value, Comparison::Eq, position)?;
+            Term::Due(comparison, date)
+        }
+        "kind" => Term::Kind(value.to_lowercase()),
+        "status" => Term::Status(TaskStatus::from_str(value, true).map_err(|_| {
+            syntax_error(format!("Invalid task status '{}'", value), position)
+        })?),
+        "has" if value == "unchecked" => Term::Unchecked,
+        "has" => {
+            return Err(syntax_error(
+                format!("Unknown term 'has:{}' (expected has:unchecked)", value),
+                position,
+            ))
+        }
+        _ if is_field => {
+            return Err(syntax_error(
+                format!("Unknown field '{}' (expected {})", field, FIELDS),
+                position,
+            ))
+        }
+        _ => Term::Text(text.to_string()),
+    };
+
+    Ok(term)
+}
+
+/// Recursive descent over the tokens. `NOT` binds tightest, then `AND`, which
+/// is also implied between adjacent terms, then `OR`.
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+    /// Position just past the end of the query, for errors at the end
+    end: usize,
+    cfg: &'a JournalConfig,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(token, _)| token)
+    }
+
+    fn parse_or(&mut self) -> JotResult<Query> {
+        let mut query = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next += 1;
+            query = Query::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> JotResult<Query> {
+        let mut query = self.parse_not()?;
+        loop {
+            match self.peek() {
+                None | Some(Token::Or) | Some(Token::Close) => return Ok(query),
+                Some(Token::And) => self.next += 1,
+                _ => {}
+            }
+            query = Query::And(Box::new(query), Box::new(self.parse_not()?));
+        }
+    }
+
+    fn parse_not(&mut self) -> JotResult<Query> {
+        if self.peek() == Some(&Token::Not) {
+            self.next += 1;
+            return Ok(Query::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> JotResult<Query> {
+        let Some((token, position)) = self.tokens.get(self.next) else {
+            return Err(syntax_error("Expected a term", self.end));
+        };
+        let position = *position;
+        self.next += 1;
+
+        match token {
+            Token::Word { text, quoted } => {
+                parse_term(text, *quoted, position, self.cfg).map(Query::Term)
+            }
+            Token::Open => {
+                let query = self.parse_or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(syntax_error("Missing ')' for the '(' opened", position));
+                }
+                self.next += 1;
+                Ok(query)
+            }
+            Token::Close => Err(syntax_error("Expected a term before ')'", position)),
+            Token::And | Token::Or => Err(syntax_error(
+                format!(
+                    "Expected a term before '{}'",
+                    if *token == Token::And { "AND" } else { "OR" }
+                ),
+                position,
+            )),
+            Token::Not => unreachable!("NOT is handled by parse_not"),
+        }
+    }
+}
+
+impl Query {
+    /// Parses a query. Terms are `field:value` pairs, metadata conditions such
+    /// as `hours>2`, or words and `"quoted phrases"` to look for in the text.
+    /// Adjacent terms must all match; `AND`, `OR`, `NOT` and parentheses
+    /// combine them otherwise.
+    ///
+    /// # Returns
+    ///
+    /// `None` for a blank query, which selects every entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `JotError::SearchError` pointing at the position of a syntax
+    /// error, an unknown field, or an invalid tag, date or status.
+    pub fn parse(query: &str, cfg: &JournalConfig) -> JotResult<Option<Self>> {
+        let tokens = tokenize(query)?;
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+
+        let mut parser = Parser {
+            tokens,
+            next: 0,
+            end: query.chars().count() + 1,
+            cfg,
+        };
+        let query = parser.parse_or()?;
+        if let Some((_, position)) = parser.tokens.get(parser.next) {
+            return Err(syntax_error("Unmatched ')'", *position));
+        }
+
+        Ok(Some(query))
+    }
+
+    /// Returns true if the entry satisfies the query
+    pub fn matches(&self, entry: &Entry, text: TextOptions<'_>) -> bool {
+        match self {
+            Query::Term(term) => term.matches(entry, text),
+            Query::Not(query) => !query.matches(entry, text),
+            Query::And(left, right) => left.matches(entry, text) && right.matches(entry, text),
+            Query::Or(left, right) => left.matches(entry, text) || right.matches(entry, text),
+        }
+    }
+
+    /// Every text term of the query, including those under a `NOT`
+    pub fn all_text_terms(&self) -> Vec<&str> {
+        match self {
+            Query::Term(Term::Text(text)) => vec![text.as_str()],
+            Query::Term(_) => Vec::new(),
+            Query::Not(query) => query.all_text_terms(),
+            Query::And(left, right) | Query::Or(left, right) => {
+                let mut terms = left.all_text_terms();
+                terms.extend(right.all_text_terms());
+                terms
+            }
+        }
+    }
+
+    /// The text terms a matching entry may contain, i.e. those not under a `NOT`
+    pub fn text_terms(&self) -> Vec<&str> {
+        match self {
+            Query::Term(Term::Text(text)) => vec![text.as_str()],
+            Query::Term(_) | Query::Not(_) => Vec::new(),
+            Query::And(left, right) | Query::Or(left, right) => {
+                let mut terms = left.text_terms();
+                terms.extend(right.text_terms());
+                terms
+            }
+        }
+    }
+}
+
+impl Term {
+    pub fn matches(&self, entry: &Entry, text: TextOptions<'_>) -> bool {
+        match self {
+            Term::Tag(tag) => super::has_tag(&entry.tags, tag, text.tag_cfg),
+            Term::Text(term) => text_matches(entry, term, text),
+            Term::Date(comparison, date) => comparison.holds(entry.date.cmp(date)),
+            Term::Modified(comparison, date) => {
+                comparison.holds(entry.last_modified().date_naive().cmp(date))
+            }
+            Term::Due(comparison, date) => entry
+                .task
+                .as_ref()
+                .and_then(|t| t.due)
+                .is_some_and(|due| comparison.holds(due.cmp(date))),
+            Term::Kind(kind) => entry.kind == *kind,
+            Term::Status(status) => entry.task.as_ref().is_some_and(|t| t.status == *status),
+            Term::Unchecked => checklist::has_unchecked(&entry.body),
+            Term::Field(condition) => condition.matches(&entry.fields),
+        }
+    }
+}
+
+/// Returns true if the term appears in the entry's title or body.
+///
+/// A term ending in `*` matches words starting with the rest. Unless the
+/// search is case-sensitive, a single word matches the start of any word and
+/// a term also matches other forms of its words, so `deploying` finds
+/// "Deployed".
+pub fn text_matches(entry: &Entry, term: &str, options: TextOptions<'_>) -> bool {
+    match options.candidates.and_then(|c| c.get(term)) {
+        Some(Candidates::Exact(ids)) => return ids.contains(&entry.id),
+        Some(Candidates::Possible(ids)) if !ids.contains(&entry.id) => return false,
+        _ => {}
+    }
+
+    let body = match &entry.title {
+        Some(title) => format!("{}\n{}", title, entry.body),
+        None => entry.body.clone(),
+    };
+
+    if options.fuzzy {
+        return fuzzy::fuzzy_match(&body, term, options.case_sensitive).is_some();
+    }
+
+    if let Some(prefix) = term.strip_suffix('*').filter(|p| !p.is_empty()) {
+        return text::has_word_with_prefix(&body, prefix);
+    }
+
+    let (body, term) = if options.case_sensitive {
+        (body, term.to_string())
+    } else {
+        (body.to_lowercase(), term.to_lowercase())
+    };
+
+    if options.case_sensitive {
+        return body.contains(&term);
+    }
+    // A single word matches the start of a word, like it does in the index
+    let found = match text::tokenize(&term).as_slice() {
+        [word] if *word == term => text::has_word_with_prefix(&body, &term),
+        _ => body.contains(&term),
+    };
+    found || text::contains_stemmed(&body, &term)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(query: &str) -> JotResult<Option<Query>> {
+        Query::parse(query, &JournalConfig::default())
+    }
+
+    fn term(term: Term) -> Box<Query> {
+        Box::new(Query::Term(term))
+    }
+
+    fn error(query: &str) -> String {
+        parse(query).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let tag = |t: &str| Term::Tag(Tag::new(t.to_string()));
+        let query = parse("tag:work AND (text:\"deploy now\" OR tag:ops) NOT tag:draft")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            query,
+            Query::And(
+                Box::new(Query::And(
+                    term(tag("work")),
+                    Box::new(Query::Or(
+                        term(Term::Text("deploy now".to_string())),
+                        term(tag("ops"))
+                    ))
+                )),
+                Box::new(Query::Not(term(tag("draft"))))
+            )
+        );
+        assert_eq!(query.text_terms(), vec!["deploy now"]);
+
+        // OR binds looser than the implied AND
+        let query = parse("a b OR c").unwrap().unwrap();
+        assert!(matches!(query, Query::Or(..)));
+
+        assert_eq!(
+            parse("date:>=2026-09-01 \"tag:x\" http://example.com")
+                .unwrap()
+                .unwrap(),
+            Query::And(
+                Box::new(Query::And(
+                    term(Term::Date(
+                        Comparison::Ge,
+                        NaiveDate::from_ymd_opt(2026, 9, 1).unwrap()
+                    )),
+                    term(Term::Text("tag:x".to_string()))
+                )),
+                term(Term::Text("http://example.com".to_string()))
+            )
+        );
+        assert_eq!(parse("  ").unwrap(), None);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(error("(tag:a OR b").contains("Missing ')' for the '(' opened at position 1"));
+        assert!(error("a) b").contains("Unmatched ')' at position 2"));
+        assert!(error("a AND").contains("Expected a term at position 6"));
+        assert!(error("OR a").contains("Expected a term before 'OR' at position 1"));
+        assert!(error("text:\"open").contains("Unterminated quote at position 6"));
+        assert!(error("x colour:red").contains("Unknown field 'colour'"));
+        assert!(error("tag:").contains("Missing value"));
+        assert!(error("tag:#").contains("Invalid tag"));
+        assert!(error("from:yesterday").contains("Invalid date"));
+        assert!(error("status:later").contains("Invalid task status"));
+        assert!(error("has:nothing").contains("expected has:unchecked"));
+        assert!(error("hours>").contains("at position 1"));
+    }
+
+    #[test]
+    fn test_tag_matches_nested_and_normalized() {
+        let cfg = JournalConfig {
+            lowercase_tags: true,
+            ..JournalConfig::default()
+        };
+        // Stored before lowercase_tags was turned on
+        let entry = Entry::new(
+            0,
+            "Billing".to_string(),
+            vec![
+                Tag::new("work/clientA".to_string()),
+                Tag::new("Ops".to_string()),
+            ],
+        );
+        let options = TextOptions {
+            tag_cfg: Some(&cfg),
+            ..TextOptions::default()
+        };
+        let matches = |query: &str| {
+            Query::parse(query, &cfg)
+                .unwrap()
+                .unwrap()
+                .matches(&entry, options)
+        };
+
+        assert!(matches("tag:work"));
+        assert!(matches("tag:work/clienta"));
+        assert!(matches("tag:ops"));
+        assert!(!matches("tag:work/clientb"));
+        assert!(!matches("tag:wor"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut entry = Entry::new(
+            0,
+            "Deployed the API".to_string(),
+            vec![Tag::new("work".to_string())],
+        );
+        entry.date = NaiveDate::from_ymd_opt(2026, 9, 10).unwrap();
+        let matches = |query: &str| {
+            parse(query)
+                .unwrap()
+                .unwrap()
+                .matches(&entry, TextOptions::default())
+        };
+
+        assert!(matches("tag:work AND (text:deploy OR tag:ops) NOT tag:draft"));
+        assert!(matches("tag:home OR api"));
+        assert!(!matches("tag:work NOT api"));
+        assert!(matches("NOT NOT deployed"));
+        assert!(matches("date:>=2026-09-01 date:<2026-10-01"));
+        assert!(!matches("date:2026-09-01"));
+        assert!(matches("kind:note OR kind:task"));
+        assert!(!matches("due:<=2026-12-31"));
+        assert!(!matches("\"the api deploy\""));
+        assert!(matches("\"deploying the api\""));
+        assert!(matches("depl* AND NOT ap*i"));
+
+        let case_sensitive = TextOptions {
+            case_sensitive: true,
+            ..TextOptions::default()
+        };
+        let api = parse("api").unwrap().unwrap();
+        assert!(!api.matches(&entry, case_sensitive));
+
+        // Terms looked up in the index only match the entries found there
+        let candidates = HashMap::from([(
+            "api".to_string(),
+            Candidates::Exact([1].into_iter().collect()),
+        )]);
+        let indexed = TextOptions {
+            candidates: Some(&candidates),
+            ..TextOptions::default()
+        };
+        assert!(!api.matches(&entry, indexed));
+        assert!(parse("deployed").unwrap().unwrap().matches(&entry, indexed));
+        assert_eq!(
+            parse("a NOT (b OR \"c d\")").unwrap().unwrap().all_text_terms(),
+            vec!["a", "b", "c d"]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use crate::storage::index::SearchIndex;
+
+use super::text;
+
+/// BM25 term frequency saturation
+const K1: f64 = 1.2;
+/// BM25 document length normalisation
+const B: f64 = 0.75;
+
+/// A word to score: a stem, or the start of words for terms like `deplo*`
+#[derive(Debug, PartialEq)]
+enum Word {
+    Stem(String),
+    Prefix(String),
+}
+
+impl Word {
+    /// The words of a search term; the words of a phrase are scored separately
+    fn from_term(term: &str) -> Vec<Word> {
+        match term.strip_suffix('*') {
+            Some(prefix) => text::tokenize(prefix).into_iter().map(Word::Prefix).collect(),
+            None => text::stems(term).into_iter().map(Word::Stem).collect(),
+        }
+    }
+}
+
+impl Word {
+    /// Weighted number of occurrences in each entry containing the word
+    fn frequencies(&self, index: &SearchIndex) -> HashMap<usize, f64> {
+        let words: Vec<&str> = match self {
+            Word::Stem(stem) => index.words_with_stem(stem).collect(),
+            Word::Prefix(prefix) => index.words_with_prefix(prefix).collect(),
+        };
+
+        let mut frequencies = HashMap::new();
+        for (id, occurrences) in words.iter().filter_map(|w| index.postings(w)).flatten() {
+            *frequencies.entry(id).or_insert(0.0) += occurrences.weight();
+        }
+        frequencies
+    }
+}
+
+/// Scores entries against the search terms with BM25, using the index for
+/// word frequencies so rare words weigh more than common ones. Title and tag
+/// words count more than body words.
+///
+/// # Returns
+///
+/// The score of each entry containing any of the words, by ID.
+pub fn bm25_scores(index: &SearchIndex, terms: &[&str]) -> HashMap<usize, f64> {
+    let mut words: Vec<Word> = Vec::new();
+    for word in terms.iter().flat_map(|term| Word::from_term(term)) {
+        if !words.contains(&word) {
+            words.push(word);
+        }
+    }
+
+    let count = index.len() as f64;
+    let average_length = index.average_length().max(1.0);
+    let mut scores = HashMap::new();
+    for word in words {
+        let frequencies = word.frequencies(index);
+        let containing = frequencies.len() as f64;
+        let idf = (1.0 + (count - containing + 0.5) / (containing + 0.5)).ln();
+
+        for (id, tf) in frequencies {
+            let norm = K1 * (1.0 - B + B * index.length(id) / average_length);
+            *scores.entry(id).or_insert(0.0) += idf * tf * (K1 + 1.0) / (tf + norm);
+        }
+    }
+    scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::entry;
+
+    #[test]
+    fn test_bm25_scores() {
+        let mut titled = entry(2, "notes from the call", &[]);
+        titled.title = Some("Deploy checklist".to_string());
+        let entries = vec![
+            entry(0, "deployed the api, then deploying the frontend", &[]),
+            entry(1, "lunch with the team", &["deploys"]),
+            titled,
+            entry(3, "long rambling notes about many things and one deploy at the end", &[]),
+        ];
+
+        let index = SearchIndex::build(&entries);
+        let score = |terms: &[&str], id| bm25_scores(&index, terms).get(&id).copied().unwrap_or(0.0);
+
+        let scores = bm25_scores(&index, &["deploying"]);
+        assert!(scores[&1] > 0.0);
+        assert!(scores[&0] > scores[&3]);
+        assert!(scores[&2] > scores[&3]);
+
+        assert_eq!(score(&["lunch"], 0), 0.0);
+        assert!(score(&["lunch"], 1) > 0.0);
+
+        assert!(score(&["front*"], 0) > 0.0);
+        assert_eq!(score(&["front*"], 3), 0.0);
+
+        // A word in every entry says little about relevance
+        assert!(score(&["api"], 0) > score(&["the"], 0));
+        assert!(bm25_scores(&index, &[]).is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::storage::Entry;
+
+use super::text;
+
+/// Share of a related entry's score that comes from its text
+const TEXT_WEIGHT: f64 = 0.7;
+/// Share of a related entry's score that comes from shared tags
+const TAG_WEIGHT: f64 = 0.3;
+
+/// TF-IDF vectors of the title and body of a set of entries, for comparing
+/// how similar their text is
+pub struct TfIdf {
+    /// Unit-length vectors by entry ID, as (word, weight) pairs sorted by word
+    vectors: HashMap<usize, Vec<(usize, f64)>>,
+}
+
+impl TfIdf {
+    pub fn new(entries: &[Entry]) -> Self {
+        let mut vocabulary: HashMap<String, usize> = HashMap::new();
+        let counts = entries
+            .iter()
+            .map(|entry| {
+                let text = format!("{}\n{}", entry.title.as_deref().unwrap_or(""), entry.body);
+                let mut counts: HashMap<usize, f64> = HashMap::new();
+                for stem in text::stems(&text) {
+                    let next = vocabulary.len();
+                    *counts
+                        .entry(*vocabulary.entry(stem).or_insert(next))
+                        .or_insert(0.0) += 1.0;
+                }
+                (entry.id, counts)
+            })
+            .collect::<Vec<_>>();
+
+        let mut containing: HashMap<usize, f64> = HashMap::new();
+        for word in counts.iter().flat_map(|(_, counts)| counts.keys()) {
+            *containing.entry(*word).or_insert(0.0) += 1.0;
+        }
+
+        // Smoothed so words in every entry still count a little, and two
+        // identical entries are fully similar even in a journal of two
+        let total = entries.len() as f64;
+        let idf = |word: &usize| ((1.0 + total) / (1.0 + containing[word])).ln() + 1.0;
+
+        let vectors = counts
+            .into_iter()
+            .map(|(id, counts)| {
+                let mut vector = counts
+                    .iter()
+                    .map(|(word, count)| (*word, count * idf(word)))
+                    .collect::<Vec<_>>();
+                let norm = vector.iter().map(|(_, w)| w * w).sum::<f64>().sqrt();
+                vector.iter_mut().for_each(|(_, w)| *w /= norm);
+                vector.sort_by_key(|(word, _)| *word);
+                (id, vector)
+            })
+            .collect();
+
+        Self { vectors }
+    }
+
+    /// Cosine similarity of the text of two entries, from 0 for no words in
+    /// common to 1 for the same words in the same proportions
+    pub fn similarity(&self, a: usize, b: usize) -> f64 {
+        let (Some(a), Some(b)) = (self.vectors.get(&a), self.vectors.get(&b)) else {
+            return 0.0;
+        };
+
+        let (mut i, mut j, mut dot) = (0, 0, 0.0);
+        while i < a.len() && j < b.len() {
+            match a[i].0.cmp(&b[j].0) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    dot += a[i].1 * b[j].1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        dot.min(1.0)
+    }
+}
+
+/// The share of the two entries' tags that they have in common
+pub fn tag_overlap(a: &Entry, b: &Entry) -> f64 {
+    let a = a
+        .tags
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect::<HashSet<_>>();
+    let b = b
+        .tags
+        .iter()
+        .map(|t| t.name.as_str())
+        .collect::<HashSet<_>>();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Scores every other entry by how much text and how many tags it shares with
+/// `entry`.
+///
+/// # Returns
+///
+/// The entries with anything in common, most related first.
+pub fn related<'a>(entries: &'a [Entry], entry: &Entry) -> Vec<(&'a Entry, f64)> {
+    let tfidf = TfIdf::new(entries);
+    let mut related = entries
+        .iter()
+        .filter(|other| other.id != entry.id)
+        .map(|other| {
+            let score = TEXT_WEIGHT * tfidf.similarity(entry.id, other.id)
+                + TAG_WEIGHT * tag_overlap(entry, other);
+            (other, score)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect::<Vec<_>>();
+    related.sort_by(|a, b| b.1.total_cmp(&a.1));
+    related
+}
+
+/// Finds pairs of entries whose text is at least `threshold` similar, such as
+/// an entry accidentally added twice.
+///
+/// Only entries sharing a word are compared. Each entry is looked up by its
+/// rarest words, and only by as many as it takes to reach the threshold: the
+/// vectors have unit length, so the words left over can't add up to it on
+/// their own.
+///
+/// # Returns
+///
+/// The IDs of each pair, lower first, with their similarity, most similar first.
+pub fn duplicates(entries: &[Entry], threshold: f64) -> Vec<(usize, usize, f64)> {
+    let tfidf = TfIdf::new(entries);
+    let mut containing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (id, vector) in &tfidf.vectors {
+        for (word, _) in vector {
+            containing.entry(*word).or_default().push(*id);
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for (&id, vector) in &tfidf.vectors {
+        let mut words = vector.clone();
+        words.sort_by_key(|(word, _)| containing[word].len());
+
+        let mut candidates = HashSet::new();
+        let mut rest = 1.0_f64;
+        for (word, weight) in words {
+            if rest.max(0.0).sqrt() < threshold {
+                break;
+            }
+            rest -= weight * weight;
+            candidates.extend(containing[&word].iter().filter(|other| **other > id));
+        }
+
+        for other in candidates {
+            let similarity = tfidf.similarity(id, other);
+            if similarity > 0.0 && similarity >= threshold {
+                pairs.push((id, other, similarity));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.2.total_cmp(&a.2).then((a.0, a.1).cmp(&(b.0, b.1))));
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::entry;
+
+    #[test]
+    fn test_similarity() {
+        let entries = vec![
+            entry(
+                0,
+                "Deployed the billing API to production",
+                &["work", "ops"],
+            ),
+            entry(1, "deploying the billing api to production", &["work"]),
+            entry(2, "Billing meeting about invoices", &["work"]),
+            entry(3, "Lunch in a park", &["personal"]),
+            entry(4, "", &[]),
+        ];
+        let tfidf = TfIdf::new(&entries);
+
+        assert!((tfidf.similarity(0, 1) - 1.0).abs() < 1e-9);
+        assert!(tfidf.similarity(0, 2) > 0.0);
+        assert_eq!(tfidf.similarity(0, 3), 0.0);
+        assert_eq!(tfidf.similarity(0, 4), 0.0);
+        assert_eq!(tfidf.similarity(0, 99), 0.0);
+        assert_eq!(tag_overlap(&entries[0], &entries[1]), 0.5);
+
+        let related = related(&entries, &entries[0]);
+        let ids = related.iter().map(|(e, _)| e.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2]);
+
+        let pairs = duplicates(&entries, 0.9);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].0, pairs[0].1), (0, 1));
+        assert!(duplicates(&entries[2..], 0.9).is_empty());
+    }
+
+    #[test]
+    fn test_duplicates_finds_every_pair() {
+        let entries = vec![
+            entry(0, "the deploy of the billing api", &[]),
+            entry(1, "the deploy of the billing service", &[]),
+            entry(2, "the lunch of the team", &[]),
+            entry(3, "billing api deploy", &[]),
+            entry(4, "the the the", &[]),
+            entry(5, "lunch with the team", &[]),
+        ];
+        let tfidf = TfIdf::new(&entries);
+
+        // Comparing only entries that share a rare word misses no pair
+        for threshold in [0.0, 0.2, 0.5, 0.8] {
+            let mut expected = Vec::new();
+            for a in 0..entries.len() {
+                for b in a + 1..entries.len() {
+                    let similarity = tfidf.similarity(a, b);
+                    if similarity > 0.0 && similarity >= threshold {
+                        expected.push((a, b));
+                    }
+                }
+            }
+            let mut found = duplicates(&entries, threshold)
+                .into_iter()
+                .map(|(a, b, _)| (a, b))
+                .collect::<Vec<_>>();
+            found.sort_unstable();
+            assert_eq!(found, expected, "threshold {}", threshold);
+        }
+    }
+}
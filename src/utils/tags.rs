@@ -0,0 +1,458 @@
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use crate::storage::{
+    config::{JournalConfig, TagNormalization},
+    index::SearchIndex,
+    Entry, Tag, TAG_SEPARATOR,
+};
+
+use super::text;
+
+/// Lowest score for a tag to be suggested
+const MIN_SUGGESTION_SCORE: f64 = 0.5;
+
+/// Turns raw text such as `#Work/ClientA,` into a tag following the tag grammar
+/// and the normalisation rules from the config.
+///
+/// A tag is made of letters, digits, `_`, `-` and `/` for nesting. Everything
+/// from the first other character on (typically trailing punctuation) is
+/// dropped, as are empty nesting levels.
+///
+/// # Returns
+///
+/// `None` if nothing of the text is a valid tag, e.g. for a lone `#`.
+pub fn normalize_tag(raw: &str, cfg: &JournalConfig) -> Option<Tag> {
+    let raw = raw.strip_prefix('#').unwrap_or(raw);
+    let normalized: String = match cfg.tag_normalization {
+        TagNormalization::None => raw.to_string(),
+        TagNormalization::Nfc => raw.nfc().collect(),
+        TagNormalization::Nfkc => raw.nfkc().collect(),
+    };
+    let normalized = if cfg.lowercase_tags {
+        normalized.to_lowercase()
+    } else {
+        normalized
+    };
+
+    let name = normalized
+        .chars()
+        .take_while(|c| is_tag_char(*c))
+        .collect::<String>()
+        .split(TAG_SEPARATOR)
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join(&TAG_SEPARATOR.to_string());
+
+    (!name.is_empty()).then(|| Tag::new(name))
+}
+
+/// Extracts the `#tags` from free text, deduplicated and in order of appearance
+pub fn extract_tags(content: &str, cfg: &JournalConfig) -> Vec<Tag> {
+    dedup_tags(
+        content
+            .split_whitespace()
+            .filter(|w| w.starts_with('#'))
+            .filter_map(|w| normalize_tag(w, cfg)),
+    )
+}
+
+/// Parses a whitespace separated list of tags where the `#` is optional
+pub fn parse_tag_list(tags: &str, cfg: &JournalConfig) -> Vec<Tag> {
+    dedup_tags(
+        tags.split_whitespace()
+            .filter_map(|t| normalize_tag(t, cfg)),
+    )
+}
+
+/// Returns true if the word is an inline tag that `extract_tags` would pick up
+pub fn is_inline_tag(word: &str, cfg: &JournalConfig) -> bool {
+    word.starts_with('#') && normalize_tag(word, cfg).is_some()
+}
+
+/// A stored tag as the current rules would write it, so tags saved before
+/// `lowercase_tags` or a normalization form was turned on still compare equal
+pub fn renormalize(tag: &Tag, cfg: &JournalConfig) -> Tag {
+    normalize_tag(&tag.name, cfg).unwrap_or_else(|| tag.clone())
+}
+
+/// Rewrites an entry's stored tags with the current normalisation rules.
+///
+/// # Returns
+///
+/// `true` if any tag changed.
+pub fn renormalize_entry(entry: &mut Entry, cfg: &JournalConfig) -> bool {
+    let tags = dedup_tags(entry.tags.iter().map(|t| renormalize(t, cfg)));
+    let changed = tags != entry.tags;
+    entry.tags = tags;
+    changed
+}
+
+/// Collects tags, keeping only the first occurrence of each
+pub fn dedup_tags(tags: impl IntoIterator<Item = Tag>) -> Vec<Tag> {
+    let mut unique: Vec<Tag> = Vec::new();
+    for tag in tags {
+        if !unique.contains(&tag) {
+            unique.push(tag);
+        }
+    }
+    unique
+}
+
+/// Renames `old` to `new` on an entry, or removes it when `new` is `None`.
+/// Tags nested below `old` move along with it, so renaming `work` to `job`
+/// turns `work/clientA` into `job/clientA`.
+///
+/// Both the tag list and inline `#old` occurrences in the body are rewritten.
+/// Tags are compared under the normalisation rules from the config, so `#Work`
+/// is renamed along with `work` when tags are lowercased. A deleted inline tag
+/// keeps its word but loses the `#`.
+///
+/// # Returns
+///
+/// `true` if the entry was changed.
+pub fn rename_tag(entry: &mut Entry, old: &Tag, new: Option<&Tag>, cfg: &JournalConfig) -> bool {
+    let mut changed = false;
+
+    if entry
+        .tags
+        .iter()
+        .any(|t| renormalize(t, cfg).is_within(old))
+    {
+        entry.tags = dedup_tags(entry.tags.drain(..).filter_map(|tag| {
+            let normalized = renormalize(&tag, cfg);
+            match new {
+                Some(new) => Some(normalized.reparent(old, new).unwrap_or(tag)),
+                None if normalized.is_within(old) => None,
+                None => Some(tag),
+            }
+        }));
+        changed = true;
+    }
+
+    if let Some(body) = replace_inline_tag(&entry.body, old, new, cfg) {
+        entry.body = body;
+        changed = true;
+    }
+
+    changed
+}
+
+/// Renames every inline `#tag` in `body` that is `tag` or nested below it to
+/// `new`, or removes its `#` when `new` is `None`. Returns `None` if the tag
+/// does not occur in the body.
+fn replace_inline_tag(
+    body: &str,
+    tag: &Tag,
+    new: Option<&Tag>,
+    cfg: &JournalConfig,
+) -> Option<String> {
+    let mut result = String::with_capacity(body.len());
+    let mut rest = body;
+    let mut found = false;
+
+    while let Some(index) = rest.find('#') {
+        let (before, after) = rest.split_at(index);
+        result.push_str(before);
+
+        // Combining marks belong to the tag even before they are composed
+        let name = &after[1..];
+        let end = name
+            .find(|c: char| !is_tag_char(c) && !is_combining_mark(c))
+            .unwrap_or(name.len());
+        let (raw, after_tag) = name.split_at(end);
+
+        let starts_word = result
+            .chars()
+            .next_back()
+            .is_none_or(|c| !is_tag_char(c) && c != '#');
+        let renamed = normalize_tag(raw, cfg)
+            .filter(|normalized| starts_word && normalized.is_within(tag))
+            .map(
+                |normalized| match new.and_then(|new| normalized.reparent(tag, new)) {
+                    Some(renamed) => format!("#{}", renamed.name),
+                    None => raw.to_string(),
+                },
+            );
+
+        match renamed {
+            Some(renamed) => {
+                result.push_str(&renamed);
+                found = true;
+            }
+            None => {
+                result.push('#');
+                result.push_str(raw);
+            }
+        }
+        rest = after_tag;
+    }
+    result.push_str(rest);
+
+    found.then_some(result)
+}
+
+/// Characters that continue a tag name when they follow it in a body
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == TAG_SEPARATOR
+}
+
+/// Suggests tags for untagged text from the tags of earlier entries using the
+/// same words.
+///
+/// A tag scores for each word of the text by the share of its entries
+/// containing the word, weighted so rare words count more than common ones.
+/// Words match in other forms and in tag names, so text mentioning a
+/// deployment suggests `#deploy`.
+///
+/// # Returns
+///
+/// At most `limit` tags, the most likely first.
+pub fn suggest_tags(
+    content: &str,
+    entries: &[Entry],
+    index: &SearchIndex,
+    limit: usize,
+) -> Vec<Tag> {
+    let tags_by_id = entries
+        .iter()
+        .map(|e| (e.id, &e.tags))
+        .collect::<HashMap<_, _>>();
+    let mut tagged: HashMap<&Tag, f64> = HashMap::new();
+    for tag in entries.iter().flat_map(|e| &e.tags) {
+        *tagged.entry(tag).or_insert(0.0) += 1.0;
+    }
+
+    let total = index.len() as f64;
+    let mut scores: HashMap<&Tag, f64> = HashMap::new();
+    for stem in text::stems(content).into_iter().collect::<HashSet<_>>() {
+        let containing = index
+            .words_with_stem(&stem)
+            .filter_map(|word| index.postings(word))
+            .flat_map(|postings| postings.into_keys())
+            .collect::<HashSet<_>>();
+        if containing.is_empty() {
+            continue;
+        }
+        let idf = (total / containing.len() as f64).ln();
+
+        let mut with_word: HashMap<&Tag, f64> = HashMap::new();
+        for tag in containing
+            .iter()
+            .filter_map(|id| tags_by_id.get(id))
+            .copied()
+            .flatten()
+        {
+            *with_word.entry(tag).or_insert(0.0) += 1.0;
+        }
+        for (tag, count) in with_word {
+            // A tag seen on a single entry is weak evidence
+            *scores.entry(tag).or_insert(0.0) += count / (tagged[tag] + 1.0) * idf;
+        }
+    }
+
+    let mut suggestions = scores
+        .into_iter()
+        .filter(|(_, score)| *score >= MIN_SUGGESTION_SCORE)
+        .collect::<Vec<_>>();
+    suggestions.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+    suggestions
+        .into_iter()
+        .take(limit)
+        .map(|(tag, _)| tag.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::entry;
+
+    fn tag(name: &str) -> Tag {
+        Tag::new(name.to_string())
+    }
+
+    #[test]
+    fn test_suggest_tags() {
+        let entries = vec![
+            entry(0, "Deployed the billing service", &["ops", "billing"]),
+            entry(1, "Rolled out the new deployment pipeline", &["ops"]),
+            entry(2, "Invoice numbers were wrong again", &["billing"]),
+            entry(3, "Lunch with the team", &[]),
+            entry(4, "Read a book about the sea", &["reading"]),
+            entry(5, "Quiet day", &[]),
+        ];
+        let index = SearchIndex::build(&entries);
+        let suggest = |content| suggest_tags(content, &entries, &index, 3);
+
+        assert_eq!(
+            suggest("deploying the auth service"),
+            vec![tag("ops"), tag("billing")]
+        );
+        assert_eq!(suggest("Fixed the invoice totals"), vec![tag("billing")]);
+        assert_eq!(suggest("more reading tonight"), vec![tag("reading")]);
+        assert!(suggest("the team").is_empty());
+        assert!(suggest("something unrelated").is_empty());
+        assert_eq!(
+            suggest_tags("deploy", &entries, &index, 1),
+            vec![tag("ops")]
+        );
+    }
+
+    #[test]
+    fn test_normalize_tag_grammar() {
+        let cfg = JournalConfig::default();
+
+        assert_eq!(normalize_tag("#work,", &cfg), Some(tag("work")));
+        assert_eq!(
+            normalize_tag("#work/clientA.", &cfg),
+            Some(tag("work/clientA"))
+        );
+        assert_eq!(normalize_tag("#a//b/", &cfg), Some(tag("a/b")));
+        assert_eq!(normalize_tag("#Work", &cfg), Some(tag("Work")));
+        assert_eq!(normalize_tag("#", &cfg), None);
+        assert_eq!(normalize_tag("#!", &cfg), None);
+        assert_eq!(normalize_tag("#/", &cfg), None);
+    }
+
+    #[test]
+    fn test_normalize_tag_rules() {
+        let cfg = JournalConfig {
+            lowercase_tags: true,
+            tag_normalization: TagNormalization::Nfkc,
+            ..JournalConfig::default()
+        };
+
+        assert_eq!(normalize_tag("#Work", &cfg), Some(tag("work")));
+        // Decomposed "e" + combining acute accent composes to "é"
+        assert_eq!(normalize_tag("#cafe\u{301}", &cfg), Some(tag("café")));
+        // Full-width letters fold to ASCII under NFKC
+        assert_eq!(normalize_tag("#\u{ff37}ork", &cfg), Some(tag("work")));
+    }
+
+    #[test]
+    fn test_extract_tags() {
+        let cfg = JournalConfig {
+            lowercase_tags: true,
+            ..JournalConfig::default()
+        };
+
+        assert_eq!(
+            extract_tags("Met #Work, then #work again # and #home!", &cfg),
+            vec![tag("work"), tag("home")]
+        );
+        assert!(is_inline_tag("#home!", &cfg));
+        assert!(!is_inline_tag("#", &cfg));
+        assert!(!is_inline_tag("home", &cfg));
+    }
+
+    #[test]
+    fn test_rename_tag() {
+        let cfg = JournalConfig::default();
+        let mut entry = Entry::new(
+            0,
+            "#wrk meeting, see (#wrk). Not #wrking or a#wrk".to_string(),
+            vec![tag("wrk"), tag("notes")],
+        );
+
+        assert!(rename_tag(
+            &mut entry,
+            &tag("wrk"),
+            Some(&tag("work")),
+            &cfg
+        ));
+        assert_eq!(entry.tags, vec![tag("work"), tag("notes")]);
+        assert_eq!(
+            entry.body,
+            "#work meeting, see (#work). Not #wrking or a#wrk"
+        );
+
+        assert!(!rename_tag(
+            &mut entry,
+            &tag("missing"),
+            Some(&tag("x")),
+            &cfg
+        ));
+    }
+
+    #[test]
+    fn test_rename_moves_subtree() {
+        let cfg = JournalConfig::default();
+        let mut entry = Entry::new(
+            0,
+            "billed #work/clientA/billing for #work".to_string(),
+            vec![tag("work"), tag("work/clientA/billing"), tag("workshop")],
+        );
+
+        assert!(rename_tag(
+            &mut entry,
+            &tag("work"),
+            Some(&tag("job")),
+            &cfg
+        ));
+        assert_eq!(
+            entry.tags,
+            vec![tag("job"), tag("job/clientA/billing"), tag("workshop")]
+        );
+        assert_eq!(entry.body, "billed #job/clientA/billing for #job");
+
+        assert!(rename_tag(&mut entry, &tag("job/clientA"), None, &cfg));
+        assert_eq!(entry.tags, vec![tag("job"), tag("workshop")]);
+        assert_eq!(entry.body, "billed job/clientA/billing for #job");
+    }
+
+    #[test]
+    fn test_rename_tag_merges_and_deletes() {
+        let cfg = JournalConfig::default();
+        let mut entry = Entry::new(0, "about #a".to_string(), vec![tag("a"), tag("b")]);
+
+        assert!(rename_tag(&mut entry, &tag("a"), Some(&tag("b")), &cfg));
+        assert_eq!(entry.tags, vec![tag("b")]);
+        assert_eq!(entry.body, "about #b");
+
+        assert!(rename_tag(&mut entry, &tag("b"), None, &cfg));
+        assert!(entry.tags.is_empty());
+        assert_eq!(entry.body, "about b");
+    }
+
+    #[test]
+    fn test_rename_tag_normalized() {
+        let cfg = JournalConfig {
+            lowercase_tags: true,
+            tag_normalization: TagNormalization::Nfc,
+            ..JournalConfig::default()
+        };
+        // Stored before the options were turned on
+        let mut entry = Entry::new(
+            0,
+            "At #Work, #WORK/Billing and #cafe\u{301}. Not #Workshop".to_string(),
+            vec![tag("Work"), tag("cafe\u{301}"), tag("Workshop")],
+        );
+
+        assert!(rename_tag(
+            &mut entry,
+            &tag("work"),
+            Some(&tag("job")),
+            &cfg
+        ));
+        assert_eq!(
+            entry.tags,
+            vec![tag("job"), tag("cafe\u{301}"), tag("Workshop")]
+        );
+        assert_eq!(
+            entry.body,
+            "At #job, #job/billing and #cafe\u{301}. Not #Workshop"
+        );
+
+        assert!(rename_tag(&mut entry, &tag("café"), None, &cfg));
+        assert_eq!(entry.tags, vec![tag("job"), tag("Workshop")]);
+        assert_eq!(
+            entry.body,
+            "At #job, #job/billing and cafe\u{301}. Not #Workshop"
+        );
+
+        assert!(renormalize_entry(&mut entry, &cfg));
+        assert_eq!(entry.tags, vec![tag("job"), tag("workshop")]);
+        assert!(!renormalize_entry(&mut entry, &cfg));
+    }
+}
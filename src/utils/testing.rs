@@ -0,0 +1,10 @@
+use crate::storage::{Entry, Tag};
+
+/// An entry with the given ID, body and tags, for tests
+pub fn entry(id: usize, body: &str, tags: &[&str]) -> Entry {
+    Entry::new(
+        id,
+        body.to_string(),
+        tags.iter().map(|t| Tag::new(t.to_string())).collect(),
+    )
+}
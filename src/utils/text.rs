@@ -0,0 +1,59 @@
+use rust_stemmers::{Algorithm, Stemmer};
+use std::sync::LazyLock;
+
+/// Created once, as stemming runs for every word indexed or searched
+static STEMMER: LazyLock<Stemmer> = LazyLock::new(|| Stemmer::create(Algorithm::English));
+
+/// Splits text into lowercase words, dropping punctuation and whitespace
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Reduces a lowercase word to its English stem, e.g. `deployed` to `deploy`
+pub fn stem(word: &str) -> String {
+    STEMMER.stem(word).into_owned()
+}
+
+/// Tokenizes and stems text
+pub fn stems(text: &str) -> Vec<String> {
+    tokenize(text).iter().map(|word| stem(word)).collect()
+}
+
+/// Returns true if the stems of `phrase` appear in `text` one after another,
+/// so `deploying the api` is found in "Deployed the API"
+pub fn contains_stemmed(text: &str, phrase: &str) -> bool {
+    let phrase = stems(phrase);
+    !phrase.is_empty()
+        && stems(text)
+            .windows(phrase.len())
+            .any(|window| window == phrase)
+}
+
+/// Returns true if any word of `text` starts with `prefix`
+pub fn has_word_with_prefix(text: &str, prefix: &str) -> bool {
+    let prefix = prefix.to_lowercase();
+    tokenize(text).iter().any(|word| word.starts_with(&prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_and_stem() {
+        assert_eq!(
+            tokenize("Deployed the API, v2.1!"),
+            vec!["deployed", "the", "api", "v2", "1"]
+        );
+        assert_eq!(stems("running deployments"), vec!["run", "deploy"]);
+
+        assert!(contains_stemmed("Deployed the API", "deploying the api"));
+        assert!(!contains_stemmed("Deployed the new API", "deploying the api"));
+        assert!(!contains_stemmed("anything", "!!"));
+        assert!(has_word_with_prefix("Kubernetes rollout", "kube"));
+        assert!(!has_word_with_prefix("Kubernetes rollout", "out"));
+    }
+}